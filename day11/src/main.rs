@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use tape_computer::Amp;
+use tape_computer::{load, Amp};
 
 type Panel = (i32, i32);
 
@@ -159,9 +159,10 @@ fn paint_panels(initial_color: Color) -> PaintingRobot {
     let mut brain = Amp::new(mem);
 
     let mut panel_color: i64 = initial_color.into();
-    while let (Some(color), Some(direction)) =
-        (brain.run(None, panel_color), brain.run(None, panel_color))
-    {
+    while let (Some(color), Some(direction)) = (
+        brain.run(None, panel_color).unwrap(),
+        brain.run(None, panel_color).unwrap(),
+    ) {
         let color = Color::from(color);
 
         robot.paint(color.clone());
@@ -172,15 +173,6 @@ fn paint_panels(initial_color: Color) -> PaintingRobot {
 }
 
 fn parse_input() -> Vec<i64> {
-    let mut memory: Vec<i64> = include_str!("day11.txt")
-        .trim()
-        .split(',')
-        .map(|c| c.parse().unwrap())
-        .collect();
-
-    (0..=4096).for_each(|_| {
-        memory.push(0);
-    });
-
-    memory
+    load::from_args_or(include_str!("day11.txt"))
+        .unwrap_or_else(|error| panic!("unable to load program: {}", error))
 }
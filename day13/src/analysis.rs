@@ -0,0 +1,258 @@
+use crate::screen::{Point, Screen, Tile};
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use tape_computer::{Amp, ChunkError, RunState};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Hit {
+    pub at: Point,
+    // How many joystick inputs had been given when the block broke.
+    pub frame: usize,
+    pub points: i64,
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Report {
+    pub inputs: Vec<i64>,
+    pub hits: Vec<Hit>,
+    pub score: i64,
+    pub won: bool,
+}
+
+impl Report {
+    // Inputs that actually move the paddle. The game reads exactly one input
+    // per frame whatever the player does, so the number of inputs is fixed
+    // by how long the game lasts; "fewest inputs" can only mean fewest
+    // paddle moves, and this is what `analyse` minimises.
+    pub fn moves(&self) -> usize {
+        self.inputs.iter().filter(|&&input| input != 0).count()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} with {} after {} inputs (one per frame), {} of them paddle moves",
+            if self.won { "won" } else { "lost" },
+            self.score,
+            self.inputs.len(),
+            self.moves()
+        )?;
+
+        for (n, hit) in self.hits.iter().enumerate() {
+            writeln!(
+                f,
+                "{:4}: frame {:5}  block ({:2}, {:2})  +{}",
+                n + 1,
+                hit.frame,
+                hit.at.0,
+                hit.at.1,
+                hit.points
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+// Where the ball will next reach the row above the paddle on its way down,
+// found by playing a copy of the game forward without touching the joystick.
+// The paddle can't change the ball's path before then, so the answer holds
+// whatever the real game does in the meantime. None if the game ends first.
+pub fn landing(amp: &Amp, screen: &Screen) -> Option<i64> {
+    let mut amp = amp.fork();
+    let mut screen = screen.clone();
+
+    loop {
+        if let Some(x) = landed(&screen) {
+            return Some(x);
+        }
+
+        amp.push_input(0);
+        match screen.read(&mut amp) {
+            Ok((RunState::NeedsInput, _)) => {}
+            _ => return None,
+        }
+    }
+}
+
+// The ball's x if it has just come down onto the paddle's row.
+fn landed(screen: &Screen) -> Option<i64> {
+    let (_, paddle_y) = screen.paddle()?;
+    let (x, y) = screen.ball()?;
+
+    match screen.ball_velocity() {
+        Some((_, 1)) if y == paddle_y - 1 => Some(x),
+        _ => None,
+    }
+}
+
+// Plays the game, moving the paddle straight to each predicted landing point
+// and then holding it there. The ball's path between landings doesn't depend
+// on the paddle, so every landing needs at least as many moves as the
+// distance from the last one: no winning sequence uses fewer paddle moves.
+// Every sequence has the same number of inputs, one per frame.
+pub fn analyse(program: Vec<i64>) -> Result<Report, Box<dyn Error>> {
+    let mut computer = Amp::new(crate::free_play(program)?);
+    let mut screen = Screen::new();
+    let mut report = Report::default();
+    let mut target = None;
+
+    while let RunState::NeedsInput = read(&mut computer, &mut screen, &mut report)? {
+        if target.is_none() {
+            target = landing(&computer, &screen);
+        }
+
+        let joystick = match (target, screen.paddle()) {
+            (Some(target), Some((paddle_x, _))) => match target.cmp(&paddle_x) {
+                Ordering::Greater => 1,
+                Ordering::Equal => 0,
+                Ordering::Less => -1,
+            },
+            _ => 0,
+        };
+
+        // The paddle still moves before the ball bounces off it, so the
+        // input on the landing frame counts towards this landing.
+        if landed(&screen).is_some() {
+            target = None;
+        }
+
+        report.inputs.push(joystick);
+        computer.push_input(joystick);
+    }
+
+    report.score = screen.score();
+    report.won = screen.blocks_remaining() == 0;
+
+    Ok(report)
+}
+
+// Like `Screen::read`, but notes each block as it breaks and credits it with
+// the score change that follows.
+fn read(amp: &mut Amp, screen: &mut Screen, report: &mut Report) -> Result<RunState, ChunkError> {
+    let mut records = amp.chunks(3);
+    for record in &mut records {
+        let (x, y, value) = (record[0], record[1], record[2]);
+
+        if (x, y) == (-1, 0) {
+            if let Some(hit) = report.hits.last_mut() {
+                hit.points += value - screen.score();
+            }
+        } else if screen.tile(x, y) == Tile::Block && Tile::from(value) != Tile::Block {
+            report.hits.push(Hit {
+                at: (x, y),
+                frame: report.inputs.len(),
+                points: 0,
+            });
+        }
+
+        screen.apply(x, y, value);
+    }
+
+    let state = records.finish()?;
+    screen.end_frame();
+
+    Ok(state)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::analysis::{analyse, landed, Hit};
+    use crate::autopilot;
+    use crate::screen::Screen;
+    use tape_computer::{load, Amp, RunState};
+
+    #[test]
+    fn credits_each_block_with_its_points() {
+        // Draws two blocks, then breaks the right one for 3 points and the
+        // left one for 2. The first word is replaced by the quarters, so it
+        // starts with a multiply that does nothing useful.
+        let mut program = vec![2, 0, 0, 0];
+        for value in &[1, 0, 2, 2, 0, 2, 2, 0, 0, -1, 0, 3, 1, 0, 0, -1, 0, 5] {
+            program.extend(&[104, *value]);
+        }
+        program.push(99);
+        let expected = vec![
+            Hit {
+                at: (2, 0),
+                frame: 0,
+                points: 3,
+            },
+            Hit {
+                at: (1, 0),
+                frame: 0,
+                points: 2,
+            },
+        ];
+
+        let actual = analyse(program).unwrap();
+
+        assert_eq!(actual.hits, expected);
+        assert_eq!(actual.score, 5);
+        assert!(actual.won);
+    }
+
+    #[test]
+    fn refuses_an_empty_program() {
+        let actual = analyse(vec![]).map(|report| report.score);
+
+        assert_eq!(actual.unwrap_err().to_string(), "the program is empty");
+    }
+
+    #[test]
+    fn wins_the_real_game_with_fewer_moves() {
+        let program = load::parse(include_str!("day13.txt")).unwrap();
+
+        // The plain autopilot, counting the inputs that move the paddle.
+        let mut memory = program.clone();
+        memory[0] = 2;
+        let mut computer = Amp::new(memory);
+        let mut screen = Screen::new();
+        let mut follower_moves = 0;
+        while let (RunState::NeedsInput, _) = screen.read(&mut computer).unwrap() {
+            let joystick = autopilot(&screen);
+            follower_moves += (joystick != 0) as usize;
+            computer.push_input(joystick);
+        }
+
+        let actual = analyse(program).unwrap();
+
+        assert!(actual.won);
+        assert_eq!(actual.score, screen.score());
+        assert_eq!(
+            actual.hits.iter().map(|hit| hit.points).sum::<i64>(),
+            actual.score
+        );
+        assert!(actual.moves() < follower_moves);
+    }
+
+    #[test]
+    fn moves_only_between_landings() {
+        let program = load::parse(include_str!("day13.txt")).unwrap();
+        let report = analyse(program.clone()).unwrap();
+
+        // Replays the inputs, adding up how far apart successive landings
+        // are, starting from where the paddle is drawn.
+        let mut memory = program;
+        memory[0] = 2;
+        let mut computer = Amp::new(memory);
+        let mut screen = Screen::new();
+        screen.read(&mut computer).unwrap();
+        let mut previous = screen.paddle().unwrap().0;
+        let mut distance = 0;
+        for &input in &report.inputs {
+            if let Some(x) = landed(&screen) {
+                distance += (x - previous).abs();
+                previous = x;
+            }
+
+            computer.push_input(input);
+            screen.read(&mut computer).unwrap();
+        }
+
+        assert_eq!(report.moves() as i64, distance);
+    }
+}
@@ -0,0 +1,185 @@
+use crate::screen::{Frame, Point, Screen};
+use crossterm::event::{self, Event, KeyCode};
+use crossterm::style::Print;
+use crossterm::terminal::{self, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{cursor, execute, queue};
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, Write};
+use std::time::Duration;
+use tape_computer::{Amp, RunState};
+
+pub enum Mode {
+    Manual,
+    Autopilot,
+    Replay(VecDeque<i64>),
+}
+
+// How long a frame stays up before the game moves on without a key press.
+const MANUAL_FRAME: Duration = Duration::from_millis(150);
+const AUTO_FRAME: Duration = Duration::from_millis(15);
+
+// Puts the terminal back however the game ends.
+struct RawScreen;
+
+impl RawScreen {
+    fn enter() -> crossterm::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, cursor::Hide)?;
+
+        Ok(RawScreen)
+    }
+}
+
+impl Drop for RawScreen {
+    fn drop(&mut self) {
+        execute!(io::stdout(), cursor::Show, LeaveAlternateScreen).ok();
+        terminal::disable_raw_mode().ok();
+    }
+}
+
+// Draws the whole board, clearing whatever was there before.
+fn render(out: &mut impl Write, screen: &Screen, status: &str) -> crossterm::Result<()> {
+    queue!(out, terminal::Clear(ClearType::All))?;
+
+    for (at, tile) in screen.tiles() {
+        if let Some((x, y)) = cell(at) {
+            queue!(out, cursor::MoveTo(x, y), Print(char::from(tile)))?;
+        }
+    }
+
+    footer(out, screen, status)
+}
+
+// Redraws only the cells that changed in `frame`.
+fn redraw(
+    out: &mut impl Write,
+    screen: &Screen,
+    frame: &Frame,
+    status: &str,
+) -> crossterm::Result<()> {
+    for change in &frame.changes {
+        if let Some((x, y)) = cell(change.at) {
+            queue!(out, cursor::MoveTo(x, y), Print(char::from(change.after)))?;
+        }
+    }
+
+    footer(out, screen, status)
+}
+
+// Where a board cell goes on the terminal. Cells above or left of the board's
+// origin, or too far away to address, aren't drawn.
+fn cell((x, y): Point) -> Option<(u16, u16)> {
+    Some((u16::try_from(x).ok()?, u16::try_from(y).ok()?))
+}
+
+fn footer(out: &mut impl Write, screen: &Screen, status: &str) -> crossterm::Result<()> {
+    let bottom = u16::try_from(screen.size().1).unwrap_or(u16::MAX - 2);
+    let velocity = match screen.ball_velocity() {
+        Some((dx, dy)) => format!("ball: {:+} {:+}", dx, dy),
+        None => String::new(),
+    };
+
+    queue!(
+        out,
+        cursor::MoveTo(0, bottom + 1),
+        terminal::Clear(ClearType::CurrentLine),
+        Print(format!("score: {}  {}", screen.score(), velocity)),
+        cursor::MoveTo(0, bottom + 2),
+        terminal::Clear(ClearType::CurrentLine),
+        Print(status)
+    )?;
+    out.flush()?;
+
+    Ok(())
+}
+
+// What the player wants to do with the next frame.
+enum Key {
+    Joystick(i64),
+    Quit,
+}
+
+fn key(timeout: Duration) -> crossterm::Result<Option<Key>> {
+    if !event::poll(timeout)? {
+        return Ok(None);
+    }
+
+    Ok(match event::read()? {
+        Event::Key(key) => match key.code {
+            KeyCode::Left | KeyCode::Char('a') => Some(Key::Joystick(-1)),
+            KeyCode::Right | KeyCode::Char('d') => Some(Key::Joystick(1)),
+            KeyCode::Down | KeyCode::Char('s') => Some(Key::Joystick(0)),
+            KeyCode::Esc | KeyCode::Char('q') => Some(Key::Quit),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+// Plays the game in the terminal, returning the score when it ends. Every
+// joystick input is written to `record`, one per line, in the format
+// `Mode::Replay` reads back.
+pub fn play(
+    program: Vec<i64>,
+    mut mode: Mode,
+    mut record: Option<File>,
+) -> Result<i64, Box<dyn Error>> {
+    let mut computer = Amp::new(crate::free_play(program)?);
+    let _screen = RawScreen::enter()?;
+    let mut out = io::stdout();
+
+    let mut screen = Screen::new();
+    let mut first = true;
+
+    let status = match mode {
+        Mode::Manual => "left/right or a/d to move, q to quit",
+        Mode::Autopilot => "autopilot, q to quit",
+        Mode::Replay(_) => "replaying, q to quit",
+    };
+
+    loop {
+        let frame = match screen.read(&mut computer) {
+            Ok((RunState::NeedsInput, frame)) => frame,
+            Ok(_) => break,
+            Err(error) => return Err(error.into()),
+        };
+
+        if first {
+            render(&mut out, &screen, status)?;
+            first = false;
+        } else {
+            redraw(&mut out, &screen, &frame, status)?;
+        }
+
+        let joystick = match &mut mode {
+            Mode::Manual => match key(MANUAL_FRAME)? {
+                Some(Key::Joystick(joystick)) => joystick,
+                Some(Key::Quit) => break,
+                None => 0,
+            },
+            Mode::Autopilot | Mode::Replay(_) => {
+                if let Some(Key::Quit) = key(AUTO_FRAME)? {
+                    break;
+                }
+
+                match &mut mode {
+                    Mode::Replay(log) => log.pop_front().unwrap_or(0),
+                    _ => crate::autopilot(&screen),
+                }
+            }
+        };
+
+        if let Some(log) = record.as_mut() {
+            writeln!(log, "{}", joystick)?;
+        }
+        computer.push_input(joystick);
+    }
+
+    render(&mut out, &screen, "game over, press any key")?;
+    event::read()?;
+
+    Ok(screen.score())
+}
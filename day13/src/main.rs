@@ -1,95 +1,157 @@
+mod analysis;
+mod arcade;
+mod screen;
+
+use arcade::Mode;
+use screen::Screen;
 use std::cmp::Ordering;
-use tape_computer::Amp;
-
-#[derive(Debug, Eq, PartialEq)]
-enum Tile {
-    Empty,
-    Wall,
-    Block,
-    HorizontalPaddle,
-    Ball,
-}
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use std::process;
+use tape_computer::{load, Amp, RunState};
+
+const USAGE: &str = "usage: day13 [program]
+       day13 --manual|--autopilot|--replay <log> [--record <log>] [program]
+       day13 --analyse [--record <log>] [program]";
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let mut mode = None;
+    let mut analyse = false;
+    let mut record = None;
+    let mut path = None;
 
-impl From<i64> for Tile {
-    fn from(input: i64) -> Self {
-        match input {
-            0 => Tile::Empty,
-            1 => Tile::Wall,
-            2 => Tile::Block,
-            3 => Tile::HorizontalPaddle,
-            4 => Tile::Ball,
-            _ => unreachable!(),
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--manual" => mode = Some(Mode::Manual),
+            "--autopilot" => mode = Some(Mode::Autopilot),
+            "--analyse" => analyse = true,
+            "--replay" => {
+                let log = args.next().unwrap_or_else(|| usage());
+                let inputs = load::from_path(&log).unwrap_or_else(|error| {
+                    eprintln!("unable to load {}: {}", log, error);
+                    process::exit(1);
+                });
+                mode = Some(Mode::Replay(inputs.into()));
+            }
+            "--record" => {
+                let log = args.next().unwrap_or_else(|| usage());
+                let file = File::create(&log).unwrap_or_else(|error| {
+                    eprintln!("unable to create {}: {}", log, error);
+                    process::exit(1);
+                });
+                record = Some(file);
+            }
+            _ if arg.starts_with("--") => usage(),
+            _ => path = Some(arg),
+        }
+    }
+
+    let program = || {
+        match &path {
+            Some(path) => load::from_path(path),
+            None => load::parse(include_str!("day13.txt")),
+        }
+        .unwrap_or_else(|error| panic!("unable to load program: {}", error))
+    };
+
+    match mode {
+        Some(_) if analyse => usage(),
+        Some(mode) => {
+            let score = arcade::play(program(), mode, record).unwrap_or_else(|error| {
+                eprintln!("unable to play: {}", error);
+                process::exit(1);
+            });
+            println!("score: {}", score);
+        }
+        // The analysis's inputs are recorded in the same format as a game's,
+        // so `--replay` can play them back. Every game takes one input per
+        // frame, so the report minimises paddle moves, not inputs.
+        None if analyse => {
+            let report = analysis::analyse(program()).unwrap_or_else(|error| {
+                eprintln!("unable to analyse: {}", error);
+                process::exit(1);
+            });
+            print!("{}", report);
+
+            if let Some(mut log) = record {
+                for input in &report.inputs {
+                    writeln!(log, "{}", input).expect("unable to write log");
+                }
+            }
+        }
+        None if record.is_some() => usage(),
+        None => {
+            println!("{}", part1());
+            println!("{:?}", part2());
         }
     }
 }
 
-fn main() {
-    println!("{}", part1());
-    println!("{:?}", part2());
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(1);
 }
 
 fn part1() -> i64 {
     let mut computer = Amp::new(parse_input());
+    let mut screen = Screen::new();
 
-    let mut block_count = 0;
-    while let (Some(_), Some(_), Some(tile)) = (
-        computer.run(None, 2),
-        computer.run(None, 2),
-        computer.run(None, 2),
-    ) {
-        if Tile::from(tile) == Tile::Block {
-            block_count += 1;
-        }
+    match screen.read(&mut computer).expect("program faulted") {
+        (RunState::Halted, _) => screen.blocks_remaining() as i64,
+        (state, _) => panic!("program did not halt: {:?}", state),
     }
-
-    block_count
 }
 
 fn part2() -> i64 {
-    let mut memory = parse_input();
-    memory[0] = 2;
+    let memory = free_play(parse_input()).unwrap_or_else(|error| {
+        eprintln!("unable to play: {}", error);
+        process::exit(1);
+    });
     let mut computer = Amp::new(memory);
+    let mut screen = Screen::new();
 
-    let mut score = 0;
-    let mut joystick_input = None;
-    let mut ball_x = 0;
-    let mut paddle_x = 0;
-    while let (Some(x), Some(y), Some(tile)) = (
-        computer.run(joystick_input, 2),
-        computer.run(joystick_input, 2),
-        computer.run(joystick_input, 2),
-    ) {
-        if x == -1 && y == 0 {
-            score = tile;
-            continue;
-        }
+    while let (RunState::NeedsInput, _) = screen.read(&mut computer).expect("program faulted") {
+        computer.push_input(autopilot(&screen));
+    }
 
-        match Tile::from(tile) {
-            Tile::Ball => ball_x = x,
-            Tile::HorizontalPaddle => paddle_x = x,
-            _ => {}
-        };
-
-        joystick_input = match ball_x.cmp(&paddle_x) {
-            Ordering::Greater => Some(1),
-            Ordering::Equal => Some(0),
-            Ordering::Less => Some(-1),
-        };
+    screen.score()
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct EmptyProgram;
+
+impl fmt::Display for EmptyProgram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "the program is empty")
     }
+}
 
-    score
+impl Error for EmptyProgram {}
+
+// Setting address 0 to 2 plays without quarters.
+fn free_play(mut program: Vec<i64>) -> Result<Vec<i64>, EmptyProgram> {
+    *program.first_mut().ok_or(EmptyProgram)? = 2;
+
+    Ok(program)
 }
 
-fn parse_input() -> Vec<i64> {
-    let mut intcode = include_str!("day13.txt")
-        .trim()
-        .split(',')
-        .map(|c| c.parse::<i64>().unwrap())
-        .collect::<Vec<_>>();
-
-    (0..4096).for_each(|_| {
-        intcode.push(0);
-    });
+// Keeps the paddle under the ball.
+fn autopilot(screen: &Screen) -> i64 {
+    match (screen.ball(), screen.paddle()) {
+        (Some((ball_x, _)), Some((paddle_x, _))) => match ball_x.cmp(&paddle_x) {
+            Ordering::Greater => 1,
+            Ordering::Equal => 0,
+            Ordering::Less => -1,
+        },
+        _ => 0,
+    }
+}
 
-    intcode
+fn parse_input() -> Vec<i64> {
+    load::from_args_or(include_str!("day13.txt"))
+        .unwrap_or_else(|error| panic!("unable to load program: {}", error))
 }
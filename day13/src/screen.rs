@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::fmt;
+use tape_computer::{Amp, ChunkError, RunState};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Tile {
+    Empty,
+    Wall,
+    Block,
+    HorizontalPaddle,
+    Ball,
+    // A tile id the cabinet doesn't define, kept so it can still be drawn.
+    Unknown(i64),
+}
+
+impl From<i64> for Tile {
+    fn from(input: i64) -> Self {
+        match input {
+            0 => Tile::Empty,
+            1 => Tile::Wall,
+            2 => Tile::Block,
+            3 => Tile::HorizontalPaddle,
+            4 => Tile::Ball,
+            _ => Tile::Unknown(input),
+        }
+    }
+}
+
+impl From<Tile> for char {
+    fn from(tile: Tile) -> Self {
+        match tile {
+            Tile::Empty => ' ',
+            Tile::Wall => '#',
+            Tile::Block => '=',
+            Tile::HorizontalPaddle => '-',
+            Tile::Ball => 'o',
+            Tile::Unknown(_) => '?',
+        }
+    }
+}
+
+pub type Point = (i64, i64);
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Change {
+    pub at: Point,
+    pub before: Tile,
+    pub after: Tile,
+}
+
+// Everything that changed between two calls to `Screen::end_frame`. A cell
+// drawn over several times in one frame shows up once, or not at all if it
+// ends up as it started.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Frame {
+    pub changes: Vec<Change>,
+    pub score: Option<i64>,
+}
+
+// The arcade cabinet's display, built from `(x, y, tile)` output triples.
+// `(-1, 0, score)` updates the segment display instead of a tile.
+#[derive(Debug, Clone, Default)]
+pub struct Screen {
+    tiles: HashMap<Point, Tile>,
+    score: i64,
+    blocks: usize,
+    ball: Option<Point>,
+    paddle: Option<Point>,
+    // The ball's position when the last frame ended, and how far it moved
+    // during that frame.
+    last_ball: Option<Point>,
+    velocity: Option<Point>,
+    pending: HashMap<Point, Tile>,
+    score_changed: bool,
+}
+
+impl Screen {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn apply(&mut self, x: i64, y: i64, value: i64) {
+        if (x, y) == (-1, 0) {
+            self.score = value;
+            self.score_changed = true;
+            return;
+        }
+
+        let tile = Tile::from(value);
+        let before = self.tiles.insert((x, y), tile).unwrap_or(Tile::Empty);
+        self.pending.entry((x, y)).or_insert(before);
+
+        if before == Tile::Block {
+            self.blocks -= 1;
+        }
+        match tile {
+            Tile::Block => self.blocks += 1,
+            Tile::Ball => self.ball = Some((x, y)),
+            Tile::HorizontalPaddle => self.paddle = Some((x, y)),
+            _ => {}
+        }
+    }
+
+    // Applies the program's output up to its next input request or halt,
+    // then ends the frame.
+    pub fn read(&mut self, amp: &mut Amp) -> Result<(RunState, Frame), ChunkError> {
+        let mut records = amp.chunks(3);
+        for record in &mut records {
+            self.apply(record[0], record[1], record[2]);
+        }
+
+        let state = records.finish()?;
+
+        Ok((state, self.end_frame()))
+    }
+
+    pub fn end_frame(&mut self) -> Frame {
+        let tiles = &self.tiles;
+        let mut changes = self
+            .pending
+            .drain()
+            .map(|(at, before)| Change {
+                at,
+                before,
+                after: tiles[&at],
+            })
+            .filter(|change| change.before != change.after)
+            .collect::<Vec<_>>();
+        changes.sort_by_key(|change| (change.at.1, change.at.0));
+
+        let score = if self.score_changed {
+            Some(self.score)
+        } else {
+            None
+        };
+        self.score_changed = false;
+
+        if let (Some((x, y)), Some((last_x, last_y))) = (self.ball, self.last_ball) {
+            self.velocity = Some((x - last_x, y - last_y));
+        }
+        self.last_ball = self.ball;
+
+        Frame { changes, score }
+    }
+
+    pub fn tile(&self, x: i64, y: i64) -> Tile {
+        self.tiles.get(&(x, y)).copied().unwrap_or(Tile::Empty)
+    }
+
+    pub fn tiles(&self) -> impl Iterator<Item = (Point, Tile)> + '_ {
+        self.tiles.iter().map(|(&at, &tile)| (at, tile))
+    }
+
+    pub fn score(&self) -> i64 {
+        self.score
+    }
+
+    pub fn blocks_remaining(&self) -> usize {
+        self.blocks
+    }
+
+    pub fn ball(&self) -> Option<Point> {
+        self.ball
+    }
+
+    pub fn paddle(&self) -> Option<Point> {
+        self.paddle
+    }
+
+    // How far the ball moved during the last frame. Only known once the
+    // ball has been drawn in two frames.
+    pub fn ball_velocity(&self) -> Option<Point> {
+        self.velocity
+    }
+
+    // The lowest and rightmost coordinates drawn so far, plus one.
+    pub fn size(&self) -> Point {
+        self.tiles
+            .keys()
+            .fold((0, 0), |(w, h), &(x, y)| (w.max(x + 1), h.max(y + 1)))
+    }
+}
+
+impl fmt::Display for Screen {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (width, height) = self.size();
+
+        for y in 0..height {
+            let row = (0..width)
+                .map(|x| char::from(self.tile(x, y)))
+                .collect::<String>();
+            writeln!(f, "{}", row.trim_end())?;
+        }
+
+        write!(f, "score: {}", self.score)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::screen::{Change, Frame, Screen, Tile};
+
+    fn draw(screen: &mut Screen, triples: &[(i64, i64, i64)]) -> Frame {
+        for &(x, y, tile) in triples {
+            screen.apply(x, y, tile);
+        }
+
+        screen.end_frame()
+    }
+
+    #[test]
+    fn tracks_board_and_score() {
+        let mut screen = Screen::new();
+
+        draw(
+            &mut screen,
+            &[
+                (0, 0, 1),
+                (1, 0, 2),
+                (2, 0, 2),
+                (1, 1, 4),
+                (1, 2, 3),
+                (-1, 0, 5),
+            ],
+        );
+
+        assert_eq!(screen.to_string(), "#==\n o\n -\nscore: 5");
+        assert_eq!(screen.blocks_remaining(), 2);
+        assert_eq!(screen.ball(), Some((1, 1)));
+        assert_eq!(screen.paddle(), Some((1, 2)));
+    }
+
+    #[test]
+    fn reports_frame_diffs_and_ball_velocity() {
+        let mut screen = Screen::new();
+        draw(&mut screen, &[(1, 0, 2), (2, 2, 4), (3, 3, 3)]);
+
+        // The ball moves up and right and knocks out the block; the paddle
+        // is redrawn where it was.
+        let frame = draw(
+            &mut screen,
+            &[(2, 2, 0), (3, 1, 4), (1, 0, 0), (3, 3, 3), (-1, 0, 4)],
+        );
+
+        assert_eq!(
+            frame,
+            Frame {
+                changes: vec![
+                    Change {
+                        at: (1, 0),
+                        before: Tile::Block,
+                        after: Tile::Empty,
+                    },
+                    Change {
+                        at: (3, 1),
+                        before: Tile::Empty,
+                        after: Tile::Ball,
+                    },
+                    Change {
+                        at: (2, 2),
+                        before: Tile::Ball,
+                        after: Tile::Empty,
+                    },
+                ],
+                score: Some(4),
+            }
+        );
+        assert_eq!(screen.ball_velocity(), Some((1, -1)));
+        assert_eq!(screen.blocks_remaining(), 0);
+    }
+
+    #[test]
+    fn keeps_unknown_tiles() {
+        let mut screen = Screen::new();
+
+        draw(&mut screen, &[(0, 0, 2), (1, 0, 7), (0, 0, 9)]);
+
+        assert_eq!(screen.tile(1, 0), Tile::Unknown(7));
+        assert_eq!(screen.to_string(), "??\nscore: 0");
+        assert_eq!(screen.blocks_remaining(), 0);
+    }
+}
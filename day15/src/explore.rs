@@ -0,0 +1,277 @@
+use crate::map::{Map, Point, Status};
+use std::collections::{HashMap, VecDeque};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use tape_computer::{Amp, IntcodeError, RunState};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    const ALL: [Direction; 4] = [
+        Direction::North,
+        Direction::East,
+        Direction::South,
+        Direction::West,
+    ];
+
+    fn step(self, (x, y): Point) -> Point {
+        match self {
+            Direction::North => (x, y + 1),
+            Direction::East => (x + 1, y),
+            Direction::South => (x, y - 1),
+            Direction::West => (x - 1, y),
+        }
+    }
+
+    fn opposite(self) -> Self {
+        match self {
+            Direction::North => Direction::South,
+            Direction::East => Direction::West,
+            Direction::South => Direction::North,
+            Direction::West => Direction::East,
+        }
+    }
+}
+
+impl From<Direction> for i64 {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::North => 1,
+            Direction::South => 2,
+            Direction::West => 3,
+            Direction::East => 4,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Strategy {
+    // Follows each corridor to its end before backing up.
+    DepthFirst,
+    // Always walks to the nearest cell with an unexplored side.
+    BreadthFirst,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ExploreError {
+    Fault(IntcodeError),
+    // The droid asked for input or halted instead of reporting a status.
+    NoStatus(RunState),
+    UnknownStatus(i64),
+}
+
+impl fmt::Display for ExploreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ExploreError::Fault(error) => write!(f, "{}", error),
+            ExploreError::NoStatus(state) => {
+                write!(f, "droid reported no status: {:?}", state)
+            }
+            ExploreError::UnknownStatus(status) => write!(f, "unknown droid status {}", status),
+        }
+    }
+}
+
+impl Error for ExploreError {}
+
+impl From<IntcodeError> for ExploreError {
+    fn from(error: IntcodeError) -> Self {
+        ExploreError::Fault(error)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Exploration {
+    pub map: Map,
+    pub moves: usize,
+    // False when the droid ran out of moves with cells still unexplored.
+    pub complete: bool,
+}
+
+// A single droid walking the maze. Every command counts as a move, whether
+// or not a wall stops it.
+struct Droid {
+    amp: Amp,
+    at: Point,
+    map: Map,
+    moves: usize,
+    max_moves: usize,
+}
+
+impl Droid {
+    fn go(&mut self, direction: Direction) -> Result<Status, ExploreError> {
+        self.amp.push_input(direction.into());
+        self.moves += 1;
+
+        let status = match self.amp.resume()? {
+            RunState::Output(status) => {
+                Status::try_from(status).map_err(ExploreError::UnknownStatus)?
+            }
+            state => return Err(ExploreError::NoStatus(state)),
+        };
+
+        let next = direction.step(self.at);
+        self.map.insert(next, status);
+        if status != Status::Wall {
+            self.at = next;
+        }
+
+        Ok(status)
+    }
+
+    fn unexplored(&self, at: Point) -> Option<Direction> {
+        Direction::ALL
+            .iter()
+            .copied()
+            .find(|direction| self.map.get(direction.step(at)).is_none())
+    }
+
+    fn out_of_moves(&self) -> bool {
+        self.moves >= self.max_moves
+    }
+
+    fn depth_first(&mut self) -> Result<bool, ExploreError> {
+        let mut path = Vec::new();
+
+        loop {
+            if let Some(direction) = self.unexplored(self.at) {
+                if self.out_of_moves() {
+                    return Ok(false);
+                }
+                if self.go(direction)? != Status::Wall {
+                    path.push(direction);
+                }
+            } else if let Some(direction) = path.pop() {
+                if self.out_of_moves() {
+                    return Ok(false);
+                }
+                self.go(direction.opposite())?;
+            } else {
+                return Ok(true);
+            }
+        }
+    }
+
+    fn breadth_first(&mut self) -> Result<bool, ExploreError> {
+        while let Some(route) = self.route_to_frontier() {
+            for direction in route {
+                if self.out_of_moves() {
+                    return Ok(false);
+                }
+                self.go(direction)?;
+            }
+        }
+
+        Ok(true)
+    }
+
+    // The shortest walk through explored cells that ends by probing an
+    // unexplored one, or None once there's nothing left to find.
+    fn route_to_frontier(&self) -> Option<Vec<Direction>> {
+        let mut came_from = HashMap::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(self.at);
+
+        while let Some(at) = queue.pop_front() {
+            if let Some(probe) = self.unexplored(at) {
+                let mut route = vec![probe];
+                let mut at = at;
+                while let Some(&(previous, direction)) = came_from.get(&at) {
+                    route.push(direction);
+                    at = previous;
+                }
+                route.reverse();
+
+                return Some(route);
+            }
+
+            for &direction in &Direction::ALL {
+                let next = direction.step(at);
+                if next != self.at && self.map.is_open(next) && !came_from.contains_key(&next) {
+                    came_from.insert(next, (at, direction));
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+// Maps the maze by driving the droid, giving up after `max_moves` commands.
+pub fn explore(
+    program: Vec<i64>,
+    strategy: Strategy,
+    max_moves: Option<usize>,
+) -> Result<Exploration, ExploreError> {
+    let mut droid = Droid {
+        amp: Amp::new(program),
+        at: (0, 0),
+        map: Map::new(),
+        moves: 0,
+        max_moves: max_moves.unwrap_or(usize::MAX),
+    };
+    droid.map.insert((0, 0), Status::Clear);
+
+    let complete = match strategy {
+        Strategy::DepthFirst => droid.depth_first()?,
+        Strategy::BreadthFirst => droid.breadth_first()?,
+    };
+
+    Ok(Exploration {
+        map: droid.map,
+        moves: droid.moves,
+        complete,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::explore::{explore, ExploreError, Strategy};
+    use tape_computer::{load, RunState};
+
+    #[test]
+    fn strategies_find_the_same_maze() {
+        let program = load::parse(include_str!("day15.txt")).unwrap();
+
+        let depth_first = explore(program.clone(), Strategy::DepthFirst, None).unwrap();
+        let breadth_first = explore(program, Strategy::BreadthFirst, None).unwrap();
+
+        assert!(depth_first.complete && breadth_first.complete);
+        assert_eq!(depth_first.map.to_string(), breadth_first.map.to_string());
+        assert_eq!(
+            depth_first.map.oxygen_system(),
+            breadth_first.map.oxygen_system()
+        );
+    }
+
+    #[test]
+    fn stops_when_out_of_moves() {
+        let program = load::parse(include_str!("day15.txt")).unwrap();
+
+        let actual = explore(program, Strategy::BreadthFirst, Some(100)).unwrap();
+
+        assert!(!actual.complete);
+        assert_eq!(actual.moves, 100);
+    }
+
+    #[test]
+    fn reports_a_droid_that_stops_answering() {
+        // Reads one command, reports a wall, then halts.
+        let program = vec![3, 0, 104, 0, 99];
+
+        let actual = explore(program, Strategy::DepthFirst, None);
+
+        assert_eq!(
+            actual.unwrap_err(),
+            ExploreError::NoStatus(RunState::Halted)
+        );
+    }
+}
@@ -1,179 +1,120 @@
-use pathfinding::directed::dijkstra::{dijkstra, dijkstra_all};
-use std::collections::HashMap;
-use tape_computer::Amp;
-
-type Grid = HashMap<(i64, i64), Status>;
-
-#[derive(Debug, Copy, Clone)]
-enum Direction {
-    North,
-    East,
-    South,
-    West,
-}
+mod explore;
+mod map;
+mod png;
+
+use explore::{explore, Strategy};
+use map::{Map, Status};
+use pathfinding::directed::dijkstra::dijkstra;
+use std::env;
+use std::fs::File;
+use std::io::BufWriter;
+use std::process;
+use tape_computer::load;
+
+const USAGE: &str =
+    "usage: day15 [--bfs] [--max-moves <n>] [--text <file>] [--png <file>] [--flood] [program]";
 
-impl Direction {
-    fn reverse(self) -> Direction {
-        match self {
-            Direction::North => Direction::South,
-            Direction::East => Direction::West,
-            Direction::South => Direction::North,
-            Direction::West => Direction::East,
+// Pixels per cell in exported images.
+const PNG_SCALE: u32 = 8;
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let mut strategy = Strategy::DepthFirst;
+    let mut max_moves = None;
+    let mut text = None;
+    let mut image = None;
+    let mut flood = false;
+    let mut path = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--bfs" => strategy = Strategy::BreadthFirst,
+            "--max-moves" => {
+                let moves = args.next().and_then(|moves| moves.parse().ok());
+                max_moves = Some(moves.unwrap_or_else(|| usage()));
+            }
+            "--text" => text = Some(args.next().unwrap_or_else(|| usage())),
+            "--png" => image = Some(args.next().unwrap_or_else(|| usage())),
+            "--flood" => flood = true,
+            _ if arg.starts_with("--") => usage(),
+            _ => path = Some(arg),
         }
     }
-}
 
-impl From<i64> for Direction {
-    fn from(direction: i64) -> Self {
-        match direction {
-            1 => Direction::North,
-            2 => Direction::South,
-            3 => Direction::West,
-            4 => Direction::East,
-            _ => unreachable!(),
-        }
+    let program = match path {
+        Some(path) => load::from_path(&path),
+        None => load::parse(include_str!("day15.txt")),
     }
-}
+    .unwrap_or_else(|error| panic!("unable to load program: {}", error));
 
-impl Into<i64> for Direction {
-    fn into(self) -> i64 {
-        match self {
-            Direction::North => 1,
-            Direction::South => 2,
-            Direction::West => 3,
-            Direction::East => 4,
+    let exploration = explore(program, strategy, max_moves).unwrap_or_else(|error| {
+        eprintln!("exploration failed: {}", error);
+        process::exit(1);
+    });
+    let map = exploration.map;
+    let bounds = map.bounds().expect("nothing explored");
+    eprintln!(
+        "mapped {}x{} in {} moves{}",
+        bounds.width(),
+        bounds.height(),
+        exploration.moves,
+        if exploration.complete {
+            ""
+        } else {
+            ", maze unfinished"
         }
+    );
+
+    if let Some(path) = text {
+        File::create(&path)
+            .and_then(|file| map.write_text(BufWriter::new(file)))
+            .unwrap_or_else(|error| panic!("unable to write {}: {}", path, error));
+    }
+    if let Some(path) = image {
+        File::create(&path)
+            .and_then(|file| map.write_png(BufWriter::new(file), PNG_SCALE))
+            .unwrap_or_else(|error| panic!("unable to write {}: {}", path, error));
     }
-}
 
-#[derive(Debug, Copy, Clone, PartialEq)]
-enum Status {
-    Wall,
-    Clear,
-    OxygenSystem,
-}
+    // The answers need the whole maze; anything mapped has been exported,
+    // but the run still failed.
+    if !exploration.complete {
+        process::exit(1);
+    }
 
-impl From<i64> for Status {
-    fn from(status: i64) -> Self {
-        match status {
-            0 => Status::Wall,
-            1 => Status::Clear,
-            2 => Status::OxygenSystem,
-            _ => unreachable!(),
+    if flood {
+        let oxygen_system = map.oxygen_system().expect("no oxygen system found");
+        for (minute, frontier) in map.flood(oxygen_system).iter().enumerate() {
+            println!("{:4}: {:?}", minute, frontier);
         }
     }
-}
 
-fn main() {
-    println!("{:?}", part1());
-    println!("{:?}", part2());
+    println!("{:?}", part1(&map));
+    println!("{:?}", part2(&map));
 }
 
-fn part1() -> usize {
-    let grid = populate_grid();
+fn usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(1);
+}
 
+fn part1(map: &Map) -> usize {
     let result = dijkstra(
         &(0, 0),
-        |point| {
-            let successors = vec![
-                (point.0 - 1, point.1),
-                (point.0 + 1, point.1),
-                (point.0, point.1 + 1),
-                (point.0, point.1 - 1),
-            ];
-
-            successors
-                .iter()
-                .filter(|&point| grid.get(point) != Some(&Status::Wall))
-                .map(|&point| (point, 1))
+        |&point| {
+            map.open_neighbours(point)
+                .map(|point| (point, 1))
                 .collect::<Vec<_>>()
         },
-        |point| grid.get(point) == Some(&Status::OxygenSystem),
+        |&point| map.get(point) == Some(Status::OxygenSystem),
     );
 
     result.expect("no result").1
 }
 
-fn part2() -> i64 {
-    let grid = populate_grid();
-
-    let &oxygen_pos = grid
-        .iter()
-        .filter(|&(_, value)| value == &Status::OxygenSystem)
-        .nth(0)
-        .expect("no oxygen system found")
-        .0;
-
-    let result = dijkstra_all(&oxygen_pos, |point| {
-        let successors = vec![
-            (point.0 - 1, point.1),
-            (point.0 + 1, point.1),
-            (point.0, point.1 + 1),
-            (point.0, point.1 - 1),
-        ];
-
-        successors
-            .iter()
-            .filter(|&point| grid.get(point) != Some(&Status::Wall))
-            .map(|&point| (point, 1))
-            .collect::<Vec<_>>()
-    });
-
-    result
-        .values()
-        .max_by_key(|(_, cost)| cost)
-        .expect("no max by key")
-        .1
-}
-
-fn populate_grid() -> Grid {
-    let mut droid = Amp::new(parse_input());
-    let mut grid = Grid::new();
-    grid.insert((0, 0), Status::Clear);
-
-    visit(&mut droid, &mut grid, (0, 0));
-
-    grid
-}
-
-fn visit(droid: &mut Amp, grid: &mut Grid, point: (i64, i64)) {
-    [
-        Direction::North,
-        Direction::East,
-        Direction::West,
-        Direction::South,
-    ]
-    .iter()
-    .for_each(|&dir| {
-        let next_pos = match dir {
-            Direction::North => (point.0, point.1 + 1),
-            Direction::East => (point.0 + 1, point.1),
-            Direction::South => (point.0, point.1 - 1),
-            Direction::West => (point.0 - 1, point.1),
-        };
-
-        if !grid.contains_key(&next_pos) {
-            let status = Status::from(droid.run(None, dir.into()).expect("no status"));
-
-            grid.insert(next_pos, status);
-
-            if status != Status::Wall {
-                visit(droid, grid, next_pos);
-
-                droid.run(None, dir.reverse().into());
-            }
-        }
-    });
-}
-
-fn parse_input() -> Vec<i64> {
-    let mut instructions = include_str!("day15.txt")
-        .trim()
-        .split(',')
-        .filter_map(|c| c.parse().ok())
-        .collect::<Vec<_>>();
-
-    (0..4096).for_each(|_| instructions.push(0));
+// Minutes until oxygen fills the area.
+fn part2(map: &Map) -> usize {
+    let oxygen_system = map.oxygen_system().expect("no oxygen system found");
 
-    instructions
+    map.flood(oxygen_system).len() - 1
 }
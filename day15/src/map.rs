@@ -0,0 +1,274 @@
+use crate::png;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{self, Write};
+
+pub type Point = (i64, i64);
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Status {
+    Wall,
+    Clear,
+    OxygenSystem,
+}
+
+// Fails with the code itself if the droid reports something else.
+impl TryFrom<i64> for Status {
+    type Error = i64;
+
+    fn try_from(status: i64) -> Result<Self, i64> {
+        match status {
+            0 => Ok(Status::Wall),
+            1 => Ok(Status::Clear),
+            2 => Ok(Status::OxygenSystem),
+            _ => Err(status),
+        }
+    }
+}
+
+impl From<Status> for char {
+    fn from(status: Status) -> Self {
+        match status {
+            Status::Wall => '#',
+            Status::Clear => '.',
+            Status::OxygenSystem => 'O',
+        }
+    }
+}
+
+// The smallest rectangle holding every explored cell, inclusive.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Bounds {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Bounds {
+    pub fn width(&self) -> i64 {
+        self.max.0 - self.min.0 + 1
+    }
+
+    pub fn height(&self) -> i64 {
+        self.max.1 - self.min.1 + 1
+    }
+
+    // Every point in the rectangle, a row at a time from north to south.
+    pub fn rows(&self) -> impl Iterator<Item = impl Iterator<Item = Point>> {
+        let (min, max) = (self.min, self.max);
+
+        (min.1..=max.1)
+            .rev()
+            .map(move |y| (min.0..=max.0).map(move |x| (x, y)))
+    }
+}
+
+// What the droid has found, with north towards positive y.
+#[derive(Debug, Clone, Default)]
+pub struct Map {
+    tiles: HashMap<Point, Status>,
+    bounds: Option<Bounds>,
+}
+
+// Wall, corridor, oxygen system and unexplored, in the exported image.
+const WALL: [u8; 3] = [0x40, 0x40, 0x40];
+const CLEAR: [u8; 3] = [0xe0, 0xe0, 0xe0];
+const OXYGEN_SYSTEM: [u8; 3] = [0x20, 0x80, 0xe0];
+const UNKNOWN: [u8; 3] = [0x00, 0x00, 0x00];
+
+impl Map {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, at: Point, status: Status) {
+        self.tiles.insert(at, status);
+
+        self.bounds = Some(match self.bounds {
+            Some(Bounds { min, max }) => Bounds {
+                min: (min.0.min(at.0), min.1.min(at.1)),
+                max: (max.0.max(at.0), max.1.max(at.1)),
+            },
+            None => Bounds { min: at, max: at },
+        });
+    }
+
+    pub fn get(&self, at: Point) -> Option<Status> {
+        self.tiles.get(&at).copied()
+    }
+
+    pub fn is_open(&self, at: Point) -> bool {
+        matches!(
+            self.get(at),
+            Some(Status::Clear) | Some(Status::OxygenSystem)
+        )
+    }
+
+    pub fn bounds(&self) -> Option<Bounds> {
+        self.bounds
+    }
+
+    pub fn oxygen_system(&self) -> Option<Point> {
+        self.tiles
+            .iter()
+            .find(|&(_, &status)| status == Status::OxygenSystem)
+            .map(|(&at, _)| at)
+    }
+
+    // Explored cells next to `at` that aren't walls.
+    pub fn open_neighbours(&self, at: Point) -> impl Iterator<Item = Point> + '_ {
+        neighbours(at).filter(move |&next| self.is_open(next))
+    }
+
+    // Spreads out from `from` through open cells one step a minute. Entry n
+    // is everything first reached at minute n, so the last entry is the
+    // frontier when the fill completes.
+    pub fn flood(&self, from: Point) -> Vec<Vec<Point>> {
+        let mut seen = HashSet::new();
+        seen.insert(from);
+        let mut frontiers = vec![vec![from]];
+
+        loop {
+            let mut next = frontiers[frontiers.len() - 1]
+                .iter()
+                .flat_map(|&at| self.open_neighbours(at))
+                .filter(|&at| seen.insert(at))
+                .collect::<Vec<_>>();
+
+            if next.is_empty() {
+                return frontiers;
+            }
+
+            next.sort_unstable();
+            frontiers.push(next);
+        }
+    }
+
+    pub fn write_text<W: Write>(&self, mut out: W) -> io::Result<()> {
+        writeln!(out, "{}", self)
+    }
+
+    // Draws each cell as a `scale` pixel square.
+    pub fn write_png<W: Write>(&self, out: W, scale: u32) -> io::Result<()> {
+        let bounds = self
+            .bounds
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "nothing explored"))?;
+        let scale = scale.max(1) as usize;
+
+        let mut pixels = Vec::new();
+        for row in bounds.rows() {
+            let row = row
+                .flat_map(|at| {
+                    let colour = match self.get(at) {
+                        Some(Status::Wall) => WALL,
+                        Some(Status::Clear) => CLEAR,
+                        Some(Status::OxygenSystem) => OXYGEN_SYSTEM,
+                        None => UNKNOWN,
+                    };
+
+                    vec![colour; scale]
+                })
+                .collect::<Vec<_>>();
+
+            for _ in 0..scale {
+                pixels.extend(&row);
+            }
+        }
+
+        png::write(
+            out,
+            (bounds.width() as usize * scale) as u32,
+            (bounds.height() as usize * scale) as u32,
+            &pixels,
+        )
+    }
+}
+
+fn neighbours(at: Point) -> impl Iterator<Item = Point> {
+    let (x, y) = at;
+
+    vec![(x, y + 1), (x + 1, y), (x, y - 1), (x - 1, y)].into_iter()
+}
+
+impl fmt::Display for Map {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let bounds = match self.bounds {
+            Some(bounds) => bounds,
+            None => return Ok(()),
+        };
+
+        let rows = bounds
+            .rows()
+            .map(|row| {
+                let row = row
+                    .map(|at| self.get(at).map_or(' ', char::from))
+                    .collect::<String>();
+
+                row.trim_end().to_string()
+            })
+            .collect::<Vec<_>>();
+
+        write!(f, "{}", rows.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::map::{Bounds, Map, Status};
+
+    // The example from part two, with its top-left corner at the origin.
+    const EXAMPLE: &str = " ##
+#..##
+#.#..#
+#.O.#
+ ###";
+
+    fn parse(text: &str) -> Map {
+        let mut map = Map::new();
+
+        for (row, line) in text.lines().enumerate() {
+            for (column, c) in line.chars().enumerate() {
+                let status = match c {
+                    '#' => Status::Wall,
+                    '.' => Status::Clear,
+                    'O' => Status::OxygenSystem,
+                    _ => continue,
+                };
+                map.insert((column as i64, -(row as i64)), status);
+            }
+        }
+
+        map
+    }
+
+    #[test]
+    fn renders_what_was_explored() {
+        let map = parse(EXAMPLE);
+
+        assert_eq!(map.to_string(), EXAMPLE);
+        assert_eq!(
+            map.bounds(),
+            Some(Bounds {
+                min: (0, -4),
+                max: (5, 0)
+            })
+        );
+        assert_eq!(map.oxygen_system(), Some((2, -3)));
+    }
+
+    #[test]
+    fn floods_the_example_in_four_minutes() {
+        let map = parse(EXAMPLE);
+        let expected = vec![
+            vec![(2, -3)],
+            vec![(1, -3), (3, -3)],
+            vec![(1, -2), (3, -2)],
+            vec![(1, -1), (4, -2)],
+            vec![(2, -1)],
+        ];
+
+        let actual = map.flood((2, -3));
+
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,78 @@
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::{self, Write};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+// Just enough PNG to write an 8-bit RGB image, row by row from the top.
+pub fn write<W: Write>(mut out: W, width: u32, height: u32, pixels: &[[u8; 3]]) -> io::Result<()> {
+    assert_eq!(
+        pixels.len(),
+        width as usize * height as usize,
+        "pixels don't fill the image"
+    );
+
+    out.write_all(&SIGNATURE)?;
+
+    let mut header = Vec::new();
+    header.extend(&width.to_be_bytes());
+    header.extend(&height.to_be_bytes());
+    // Bit depth, RGB, deflate, adaptive filtering, no interlacing.
+    header.extend(&[8, 2, 0, 0, 0]);
+    chunk(&mut out, b"IHDR", &header)?;
+
+    // Every row is left unfiltered.
+    let mut data = ZlibEncoder::new(Vec::new(), Compression::default());
+    for row in pixels.chunks(width.max(1) as usize) {
+        data.write_all(&[0])?;
+        for pixel in row {
+            data.write_all(pixel)?;
+        }
+    }
+    chunk(&mut out, b"IDAT", &data.finish()?)?;
+
+    chunk(&mut out, b"IEND", &[])?;
+    out.flush()
+}
+
+fn chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(kind);
+    crc.update(data);
+
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    out.write_all(&crc.finalize().to_be_bytes())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::png::write;
+    use flate2::read::ZlibDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn writes_a_decodable_image() {
+        let mut actual = Vec::new();
+
+        write(&mut actual, 2, 1, &[[255, 0, 0], [0, 0, 255]]).unwrap();
+
+        assert_eq!(&actual[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&actual[8..16], b"\x00\x00\x00\x0dIHDR");
+        assert_eq!(&actual[16..29], &[0, 0, 0, 2, 0, 0, 0, 1, 8, 2, 0, 0, 0]);
+        // Every empty IEND chunk is the same, checksum included.
+        assert_eq!(
+            &actual[actual.len() - 12..],
+            b"\x00\x00\x00\x00IEND\xae\x42\x60\x82"
+        );
+
+        let length = u32::from_be_bytes([actual[33], actual[34], actual[35], actual[36]]) as usize;
+        assert_eq!(&actual[37..41], b"IDAT");
+        let mut pixels = Vec::new();
+        ZlibDecoder::new(&actual[41..41 + length])
+            .read_to_end(&mut pixels)
+            .unwrap();
+        assert_eq!(pixels, vec![0, 255, 0, 0, 0, 0, 255]);
+    }
+}
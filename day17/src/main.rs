@@ -1,5 +1,6 @@
-use std::collections::{HashMap, VecDeque};
-use tape_computer::Amp;
+use std::collections::HashMap;
+use tape_computer::ascii::{Ascii, Event};
+use tape_computer::{load, Amp};
 
 type Point = (i32, i32);
 type Grid = HashMap<Point, char>;
@@ -10,7 +11,7 @@ fn main() {
 }
 
 fn part1() -> i32 {
-    let mut droid = Amp::new(parse_input());
+    let mut droid = Ascii::new(Amp::new(parse_input()));
 
     let (grid, grid_image) = populate_grid(&mut droid);
     let intersections = find_intersections(&grid);
@@ -23,49 +24,51 @@ fn part1() -> i32 {
 // HACK: I calculated the path by hand and then factored the functions
 // I should have done this via some sort of compression algorithm and
 // a graph discovery, I think?
+const MOVEMENT: &str = "A,B,A,B,A,C,B,C,A,C
+L,10,L,12,R,6
+R,10,L,4,L,4,L,12
+L,10,R,10,R,6,L,4
+n";
+
 fn part2() -> i64 {
     let mut memory = parse_input();
     memory[0] = 2;
-    let mut droid = Amp::new(memory);
-
-    let instructions: VecDeque<i64> =
-        "A,B,A,B,A,C,B,C,A,C\nL,10,L,12,R,6\nR,10,L,4,L,4,L,12\nL,10,R,10,R,6,L,4\nn\ny\n"
-            .chars()
-            .map(|c| c as u8 as i64)
-            .collect();
-
-    droid.input_buffer = instructions;
+    let mut droid = Ascii::new(Amp::new(memory));
 
-    while let Some(output) = droid.run(None, 0) {
-        if !(output as u8 as char).is_ascii() {
-            return output;
-        }
+    for line in MOVEMENT.lines() {
+        droid.send_line(line).unwrap();
     }
 
-    0
+    let events = droid.drain().expect("program faulted");
+    events
+        .into_iter()
+        .find_map(|event| match event {
+            Event::Value(dust) => Some(dust),
+            _ => None,
+        })
+        .expect("no dust collected")
 }
 
-fn populate_grid(droid: &mut Amp) -> (Grid, Vec<String>) {
+fn populate_grid(droid: &mut Ascii) -> (Grid, Vec<String>) {
     let mut grid = Grid::new();
     let mut buffer = Vec::new();
-    let mut line = String::new();
-
-    let mut row = 0;
-    let mut col = 0;
-    while let Some(output) = droid.run(None, 0) {
-        if output == 10 {
-            row += 1;
-            col = 0;
-            buffer.push(line.clone());
-            line.clear();
-            continue;
-        }
 
-        let c = output as u8 as char;
-        line.push(c);
-        grid.insert((row, col), c);
+    let events = droid.drain().expect("program faulted");
+    if let Some(event) = events.last().filter(|&event| *event != Event::Halted) {
+        panic!("camera did not halt: {:?}", event);
+    }
+
+    for event in events {
+        let line = match event {
+            Event::Line(line) => line,
+            _ => continue,
+        };
 
-        col += 1;
+        let row = buffer.len() as i32;
+        for (col, c) in line.chars().enumerate() {
+            grid.insert((row, col as i32), c);
+        }
+        buffer.push(line);
     }
 
     (grid, buffer)
@@ -101,15 +104,6 @@ fn print_grid(grid_image: &Vec<String>) {
 }
 
 fn parse_input() -> Vec<i64> {
-    let mut intcode = include_str!("day17.txt")
-        .trim()
-        .split(",")
-        .filter_map(|c| c.parse().ok())
-        .collect::<Vec<_>>();
-
-    (0..4096).for_each(|_| {
-        intcode.push(0);
-    });
-
-    intcode
+    load::from_args_or(include_str!("day17.txt"))
+        .unwrap_or_else(|error| panic!("unable to load program: {}", error))
 }
@@ -1,93 +1,46 @@
+use tape_computer::symbolic::Symbolic;
+use tape_computer::{load, Amp, RunState};
+
 fn main() {
     println!("part 1: {}", part1());
     println!("part 2: {}", part2());
 }
 
-enum Opcode {
-    Add,
-    Mul,
-}
-
-impl From<i64> for Opcode {
-    fn from(input: i64) -> Self {
-        match input {
-            1 => Self::Add,
-            2 => Self::Mul,
-            _ => unimplemented!(),
-        }
-    }
-}
-
 fn part1() -> i64 {
     let mut input = parse_input();
 
     input[1] = 12;
     input[2] = 2;
 
-    let mut index = 0 as usize;
-    loop {
-        if input[index] == 99 {
-            break;
-        }
-        let opcode = Opcode::from(input[index]);
-        let (operand_pos1, operand_pos2) = (input[index + 1] as usize, input[index + 2] as usize);
-        let output_pos = input[index + 3] as usize;
-
-        match opcode {
-            Opcode::Add => input[output_pos] = input[operand_pos1] + input[operand_pos2],
-            Opcode::Mul => input[output_pos] = input[operand_pos1] * input[operand_pos2],
-        };
-
-        index += 4;
-    }
-
-    input[0]
+    run_tape(input)[0]
 }
 
 fn part2() -> i64 {
     const OUTPUT_VALUE: i64 = 19_690_720;
 
-    let input = parse_input();
+    let mut machine = Symbolic::new(&parse_input());
+    let noun = machine.symbol_at(1, "noun", 0..=99);
+    let verb = machine.symbol_at(2, "verb", 0..=99);
+    machine.run().expect("program faulted");
 
-    for noun in 1..99 {
-        for verb in 1..99 {
-            let mut tape = input.clone();
-            tape[1] = noun;
-            tape[2] = verb;
-            if run_tape(tape)[0] == OUTPUT_VALUE {
-                return 100 * noun + verb;
-            }
-        }
-    }
+    let solution = machine
+        .solve(&machine.memory(0), OUTPUT_VALUE)
+        .expect("output isn't linear in noun and verb")
+        .expect("no noun and verb produce the output");
 
-    unreachable!()
+    100 * solution[noun] + solution[verb]
 }
 
 fn parse_input() -> Vec<i64> {
-    include_str!("day2.txt")
-        .trim()
-        .split(',')
-        .map(|input| input.parse::<i64>().expect("unable to parse"))
-        .collect::<Vec<i64>>()
+    load::from_args_or(include_str!("day2.txt"))
+        .unwrap_or_else(|error| panic!("unable to load program: {}", error))
 }
 
-fn run_tape(mut tape: Vec<i64>) -> Vec<i64> {
-    let mut index = 0 as usize;
-    loop {
-        if tape[index] == 99 {
-            break;
-        }
-        let opcode = Opcode::from(tape[index]);
-        let (operand_pos1, operand_pos2) = (tape[index + 1] as usize, tape[index + 2] as usize);
-        let output_pos = tape[index + 3] as usize;
-
-        match opcode {
-            Opcode::Add => tape[output_pos] = tape[operand_pos1] + tape[operand_pos2],
-            Opcode::Mul => tape[output_pos] = tape[operand_pos1] * tape[operand_pos2],
-        };
+fn run_tape(tape: Vec<i64>) -> Vec<i64> {
+    let mut computer = Amp::new(tape);
 
-        index += 4;
+    match computer.resume().expect("program faulted") {
+        RunState::Halted => computer.memory().to_vec(),
+        state => panic!("program did not halt: {:?}", state),
     }
-
-    tape
 }
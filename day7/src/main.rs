@@ -1,5 +1,5 @@
-use itertools::Itertools;
-use tape_computer::Amp;
+use tape_computer::load;
+use tape_computer::phase::{search, Wiring};
 
 fn main() {
     println!("{}", part1());
@@ -7,52 +7,18 @@ fn main() {
 }
 
 fn part1() -> i64 {
-    let tape = parse_input();
+    let best = search(&parse_input(), &[0, 1, 2, 3, 4], 5, Wiring::Serial).unwrap();
 
-    (0..=4)
-        .permutations(5)
-        .map(|perm| {
-            let mut signal = 0;
-            for p in perm {
-                let mut amp = Amp::new(tape.clone());
-
-                signal = amp.run(Some(p), signal).unwrap();
-            }
-
-            signal
-        })
-        .max()
-        .expect("no maximum found")
+    best.expect("no maximum found").signal
 }
 
 fn part2() -> i64 {
-    let tape = parse_input();
-
-    (5..=9)
-        .permutations(5)
-        .map(|perm| {
-            let mut signal = 0;
-            let mut amps = vec![Amp::new(tape.clone()); 5];
-            let mut input_iter = perm.iter().cloned();
-
-            for amp_id in (0..amps.len()).cycle() {
-                if let Some(s) = amps[amp_id].run(input_iter.next(), signal) {
-                    signal = s;
-                } else {
-                    break;
-                }
-            }
+    let best = search(&parse_input(), &[5, 6, 7, 8, 9], 5, Wiring::Feedback).unwrap();
 
-            signal
-        })
-        .max()
-        .expect("no max found")
+    best.expect("no max found").signal
 }
 
 fn parse_input() -> Vec<i64> {
-    include_str!("day7.txt")
-        .trim()
-        .split(',')
-        .map(|inst| inst.parse().unwrap())
-        .collect()
+    load::from_args_or(include_str!("day7.txt"))
+        .unwrap_or_else(|error| panic!("unable to load program: {}", error))
 }
@@ -1,4 +1,4 @@
-use tape_computer::Amp;
+use tape_computer::{load, Amp};
 
 fn main() {
     println!("{:?}", part1());
@@ -9,32 +9,28 @@ fn part1() -> i64 {
     let memory = parse_input();
 
     let mut computer = Amp::new(memory);
-    computer.run(None, 1).expect("no output")
+    computer.run(None, 1).unwrap().expect("no output")
 }
 fn part2() -> i64 {
     let memory = parse_input();
 
     let mut computer = Amp::new(memory);
-    computer.run(None, 2).expect("no output")
+    computer.run(None, 2).unwrap().expect("no output")
 }
 
 fn parse_input() -> Vec<i64> {
-    let mut memory: Vec<i64> = include_str!("day9.txt")
-        .trim()
-        .split(',')
-        .map(|c| c.parse().unwrap())
-        .collect();
-
-    (0..=4096).for_each(|_| {
-        memory.push(0);
-    });
-
-    memory
+    load::from_args_or(include_str!("day9.txt"))
+        .unwrap_or_else(|error| panic!("unable to load program: {}", error))
 }
 
 #[cfg(test)]
 mod test {
-    use tape_computer::Amp;
+    use num_bigint::BigInt;
+    use tape_computer::bigint::BigAmp;
+    use tape_computer::{Amp, IntcodeError, Overflow, RunState};
+
+    // Multiplies two immediates past the range of an i64 and prints the result.
+    const OVERFLOWING: [i64; 7] = [1102, 1125899906842624, 1 << 20, 7, 4, 7, 99];
 
     #[test]
     fn outputs_large_number() {
@@ -42,8 +38,39 @@ mod test {
         let expected: i64 = 1125899906842624;
 
         let mut amp = Amp::new(instructions);
-        let actual = amp.run(None, 0).unwrap();
+        let actual = amp.run(None, 0).unwrap().unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn reports_overflow_by_default() {
+        let expected = Err(IntcodeError::Overflow { pc: 0 });
+
+        let mut amp = Amp::new(OVERFLOWING.to_vec());
+        let actual = amp.resume();
 
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn wraps_or_saturates_on_request() {
+        let mut wrapping = Amp::new(OVERFLOWING.to_vec());
+        wrapping.set_overflow(Overflow::Wrapping);
+        let mut saturating = Amp::new(OVERFLOWING.to_vec());
+        saturating.set_overflow(Overflow::Saturating);
+
+        assert_eq!(wrapping.resume(), Ok(RunState::Output(0)));
+        assert_eq!(saturating.resume(), Ok(RunState::Output(i64::MAX)));
+    }
+
+    #[test]
+    fn outputs_product_too_large_for_i64() {
+        let expected = BigInt::from(1125899906842624i64) * BigInt::from(1 << 20);
+
+        let mut amp = BigAmp::new(OVERFLOWING.to_vec());
+        let actual = amp.resume();
+
+        assert_eq!(actual, Ok(RunState::Output(expected)));
+    }
 }
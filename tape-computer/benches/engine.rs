@@ -0,0 +1,62 @@
+use std::time::{Duration, Instant};
+use tape_computer::{Amp, IntcodeError, RunState};
+
+const BOOST: &str = include_str!("../../day9/src/day9.txt");
+const AMPLIFIER: &str = include_str!("../../day7/src/day7.txt");
+
+fn parse(source: &str) -> Vec<i64> {
+    source
+        .trim()
+        .split(',')
+        .map(|word| word.parse().expect("not a number"))
+        .collect()
+}
+
+fn run<F>(program: &[i64], inputs: &[i64], resume: F) -> Vec<i64>
+where
+    F: Fn(&mut Amp) -> Result<RunState, IntcodeError>,
+{
+    let mut amp = Amp::new(program.to_vec());
+    amp.extend_input(inputs.iter().copied());
+
+    let mut outputs = Vec::new();
+    while let RunState::Output(value) = resume(&mut amp).unwrap() {
+        outputs.push(value);
+    }
+
+    outputs
+}
+
+fn time<F: FnMut() -> Vec<i64>>(iterations: u32, mut f: F) -> (Duration, Vec<i64>) {
+    let result = f();
+
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+
+    (start.elapsed() / iterations, result)
+}
+
+fn bench(name: &str, iterations: u32, program: &[i64], inputs: &[i64]) {
+    let (slow, expected) = time(iterations, || run(program, inputs, Amp::resume));
+    let (fast, actual) = time(iterations, || run(program, inputs, Amp::resume_fast));
+    assert_eq!(actual, expected, "{}: engines disagree", name);
+
+    println!(
+        "{:<20} step {:>10.3?}  fast {:>10.3?}  ({:.2}x)",
+        name,
+        slow,
+        fast,
+        slow.as_secs_f64() / fast.as_secs_f64()
+    );
+}
+
+fn main() {
+    let boost = parse(BOOST);
+    let amplifier = parse(AMPLIFIER);
+
+    bench("day9 part 1", 1000, &boost, &[1]);
+    bench("day9 part 2", 5, &boost, &[2]);
+    bench("day7 amplifier", 10000, &amplifier, &[4, 0]);
+}
@@ -0,0 +1,348 @@
+use crate::disasm::{self, decode_at, disassemble, successors, Item, Line, Listing};
+use crate::{Mode, Opcode};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Write};
+use std::ops::Range;
+
+// A straight run of instructions that is only entered at the top and only
+// left at the bottom.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Block {
+    pub start: usize,
+    pub end: usize,
+    pub instructions: Vec<usize>,
+    pub successors: Vec<usize>,
+    // Ends in a jump whose target is only known at run time.
+    pub indirect: bool,
+}
+
+// A write whose target is known statically and lands on reachable code.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct CodeWrite {
+    pub site: usize,
+    pub target: usize,
+    pub instruction: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    pub entry: usize,
+    pub listing: Listing,
+    pub blocks: BTreeMap<usize, Block>,
+    pub dead: Vec<Range<usize>>,
+    pub code_writes: Vec<CodeWrite>,
+    pub inputs: Vec<usize>,
+    pub outputs: Vec<usize>,
+}
+
+impl Analysis {
+    // Reachability follows the disassembler: static successors from `entry`,
+    // plus the return addresses pushed before subroutine calls. Jumps through
+    // memory can't be followed, so code only reached that way looks dead.
+    pub fn new(program: &[i64], entry: usize) -> Self {
+        let listing = disassemble(program, entry);
+        let code = listing
+            .lines
+            .iter()
+            .filter_map(|line| match &line.item {
+                Item::Instruction { opcode, params } => Some((line.addr, (*opcode, params))),
+                Item::Data(_) => None,
+            })
+            .collect::<BTreeMap<_, _>>();
+
+        let mut leaders = BTreeSet::new();
+        leaders.insert(entry);
+        for (&addr, &(opcode, params)) in &code {
+            leaders.extend(disasm::return_site(program, addr, opcode, params));
+            if let Opcode::JIT(_, _) | Opcode::JIF(_, _) = opcode {
+                leaders.extend(successors(addr, opcode, params));
+            }
+        }
+
+        let mut blocks = BTreeMap::new();
+        let mut current: Option<Block> = None;
+        for (&addr, &(opcode, params)) in &code {
+            let next = addr + params.len() + 1;
+            let block = current.get_or_insert_with(|| Block {
+                start: addr,
+                end: addr,
+                instructions: vec![],
+                successors: vec![],
+                indirect: false,
+            });
+            block.instructions.push(addr);
+            block.end = next;
+
+            let branches = matches!(opcode, Opcode::JIT(_, _) | Opcode::JIF(_, _) | Opcode::Halt);
+            if branches || leaders.contains(&next) || !code.contains_key(&next) {
+                let mut block = current.take().unwrap();
+                block.successors = successors(addr, opcode, params)
+                    .into_iter()
+                    .filter(|target| code.contains_key(target))
+                    .collect();
+                block.indirect = match opcode {
+                    Opcode::JIT(_, m2) | Opcode::JIF(_, m2) => m2 != Mode::Immediate,
+                    _ => false,
+                };
+                blocks.insert(block.start, block);
+            }
+        }
+
+        let mut code_writes = Vec::new();
+        let mut inputs = Vec::new();
+        let mut outputs = Vec::new();
+        for (&site, &(opcode, params)) in &code {
+            match opcode {
+                Opcode::Save(_) => inputs.push(site),
+                Opcode::Output(_) => outputs.push(site),
+                _ => {}
+            }
+
+            let target = match (opcode.writes(), opcode.modes().last()) {
+                (true, Some(Mode::Position)) if params[params.len() - 1] >= 0 => {
+                    params[params.len() - 1] as usize
+                }
+                _ => continue,
+            };
+            if let Some((&instruction, &(_, params))) = code.range(..=target).next_back() {
+                if target <= instruction + params.len() {
+                    code_writes.push(CodeWrite {
+                        site,
+                        target,
+                        instruction,
+                    });
+                }
+            }
+        }
+
+        Analysis {
+            entry,
+            dead: dead_code(program, &listing),
+            listing,
+            blocks,
+            code_writes,
+            inputs,
+            outputs,
+        }
+    }
+
+    pub fn is_reachable(&self, addr: usize) -> bool {
+        self.blocks
+            .range(..=addr)
+            .next_back()
+            .is_some_and(|(_, block)| addr < block.end)
+    }
+
+    // The control-flow graph in Graphviz DOT, one box per block. Blocks that
+    // end in an indirect jump are dashed, and I/O sites and self-modifying
+    // writes are called out in the instruction text.
+    pub fn to_dot(&self) -> String {
+        let lines = self
+            .listing
+            .lines
+            .iter()
+            .map(|line| (line.addr, line))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut dot =
+            String::from("digraph cfg {\n    node [shape=box, fontname=\"monospace\"];\n");
+        for block in self.blocks.values() {
+            let mut label = String::new();
+            for addr in &block.instructions {
+                write!(label, "{}", escape(&self.describe(lines[addr]))).unwrap();
+                label.push_str("\\l");
+            }
+
+            let style = if block.indirect { ", style=dashed" } else { "" };
+            writeln!(dot, "    b{} [label=\"{}\"{}];", block.start, label, style).unwrap();
+        }
+
+        writeln!(dot, "    entry [shape=point];").unwrap();
+        writeln!(dot, "    entry -> b{};", self.entry).unwrap();
+        for block in self.blocks.values() {
+            for target in &block.successors {
+                writeln!(dot, "    b{} -> b{};", block.start, target).unwrap();
+            }
+        }
+        dot.push_str("}\n");
+
+        dot
+    }
+
+    fn describe(&self, line: &Line) -> String {
+        let mut text = line.to_string();
+        if self.inputs.contains(&line.addr) {
+            text.push_str("  ; input");
+        }
+        if self.outputs.contains(&line.addr) {
+            text.push_str("  ; output");
+        }
+        for write in self.code_writes.iter().filter(|w| w.site == line.addr) {
+            write!(text, "  ; modifies code at {}", write.instruction).unwrap();
+        }
+
+        text
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// Unreachable words that still decode as a run of instructions ending in a
+// halt or an unconditional jump. Tables of small numbers can pass for code,
+// so this is a hint rather than a proof.
+fn dead_code(program: &[i64], listing: &Listing) -> Vec<Range<usize>> {
+    let mut code = vec![false; program.len()];
+    for line in &listing.lines {
+        if let Item::Instruction { params, .. } = &line.item {
+            for covered in &mut code[line.addr..=line.addr + params.len()] {
+                *covered = true;
+            }
+        }
+    }
+
+    let mut dead = Vec::new();
+    let mut addr = 0;
+    while addr < program.len() {
+        let start = addr;
+        let mut end = None;
+
+        while let Some((opcode, params)) = decode_at(program, addr) {
+            let next = addr + params.len() + 1;
+            if code[addr..next].iter().any(|&covered| covered) {
+                break;
+            }
+
+            addr = next;
+            if !falls_through(opcode, &params) {
+                end = Some(next);
+                break;
+            }
+        }
+
+        match end {
+            Some(end) => dead.push(start..end),
+            None => addr = start + 1,
+        }
+    }
+
+    dead
+}
+
+fn falls_through(opcode: Opcode, params: &[i64]) -> bool {
+    match opcode {
+        Opcode::Halt => false,
+        Opcode::JIT(Mode::Immediate, _) => params[0] == 0,
+        Opcode::JIF(Mode::Immediate, _) => params[0] != 0,
+        _ => true,
+    }
+}
+
+fn join<T: fmt::Display>(items: impl Iterator<Item = T>) -> String {
+    items
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl fmt::Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let dead = self.dead.iter().map(|r| format!("{}..{}", r.start, r.end));
+
+        writeln!(f, "entry: {}", self.entry)?;
+        writeln!(f, "blocks: {}", self.blocks.len())?;
+        writeln!(f, "inputs: {}", join(self.inputs.iter()))?;
+        writeln!(f, "outputs: {}", join(self.outputs.iter()))?;
+        writeln!(f, "dead: {}", join(dead))?;
+        for write in &self.code_writes {
+            writeln!(
+                f,
+                "code write: {} writes {} (instruction at {})",
+                write.site, write.target, write.instruction
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::analysis::{Analysis, CodeWrite};
+
+    // Reads numbers and echoes them until one is zero.
+    const ECHO: [i64; 10] = [3, 9, 4, 9, 1005, 9, 0, 99, 0, 0];
+
+    #[test]
+    fn splits_blocks_at_jumps_and_targets() {
+        let analysis = Analysis::new(&ECHO, 0);
+
+        let shape = analysis
+            .blocks
+            .values()
+            .map(|block| (block.start, block.end, block.successors.clone()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(shape, vec![(0, 7, vec![7, 0]), (7, 8, vec![])]);
+        assert_eq!(analysis.inputs, vec![0]);
+        assert_eq!(analysis.outputs, vec![2]);
+    }
+
+    #[test]
+    fn finds_dead_code_after_halt() {
+        let program = vec![104, 1, 99, 1101, 2, 3, 0, 99, 0];
+
+        let analysis = Analysis::new(&program, 0);
+
+        assert_eq!(analysis.dead, vec![3..8]);
+        assert!(analysis.is_reachable(1));
+        assert!(!analysis.is_reachable(3));
+    }
+
+    #[test]
+    fn survives_overflowing_operands() {
+        // The pushed "return address" overflows; the jump after it never
+        // branches, so the halt is unreachable.
+        let program = vec![21101, i64::MAX, 1, 0, 1106, 0, 0, 99];
+
+        let analysis = Analysis::new(&program, 0);
+
+        assert_eq!(analysis.blocks.keys().copied().collect::<Vec<_>>(), vec![0]);
+        assert_eq!(analysis.dead, vec![7..8]);
+    }
+
+    #[test]
+    fn flags_writes_into_code() {
+        // Overwrites the parameter of the output instruction, then its opcode.
+        let program = vec![1101, 7, 0, 9, 1101, 0, 99, 10, 104, 0, 104, 1, 99];
+        let expected = vec![
+            CodeWrite {
+                site: 0,
+                target: 9,
+                instruction: 8,
+            },
+            CodeWrite {
+                site: 4,
+                target: 10,
+                instruction: 10,
+            },
+        ];
+
+        let actual = Analysis::new(&program, 0).code_writes;
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn exports_dot() {
+        let dot = Analysis::new(&ECHO, 0).to_dot();
+
+        assert!(dot.starts_with("digraph cfg {\n"));
+        assert!(
+            dot.contains("    b0 [label=\"0000: IN -> [9]  ; input\\l0002: OUT [9]  ; output\\l")
+        );
+        assert!(dot.contains("    b0 -> b7;\n    b0 -> b0;\n"));
+        assert!(dot.ends_with("}\n"));
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{Amp, IntcodeError, RunState};
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Event {
+    // A line of text, without its newline. Text the program leaves
+    // unterminated before a value, an input request or a halt comes out as
+    // a line of its own.
+    Line(String),
+    // An output outside the ASCII range, such as a puzzle answer.
+    Value(i64),
+    NeedsInput,
+    Halted,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct NotAscii(pub char);
+
+impl fmt::Display for NotAscii {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?} is not an ASCII character", self.0)
+    }
+}
+
+impl Error for NotAscii {}
+
+// Drives a program that talks in ASCII text, a line at a time.
+#[derive(Debug, Clone)]
+pub struct Ascii {
+    amp: Amp,
+    partial: String,
+    pending: Option<i64>,
+}
+
+impl Ascii {
+    pub fn new(amp: Amp) -> Self {
+        Self {
+            amp,
+            partial: String::new(),
+            pending: None,
+        }
+    }
+
+    pub fn amp(&self) -> &Amp {
+        &self.amp
+    }
+
+    pub fn amp_mut(&mut self) -> &mut Amp {
+        &mut self.amp
+    }
+
+    pub fn into_inner(self) -> Amp {
+        self.amp
+    }
+
+    // Queues `line` followed by a newline. Nothing is queued if any of it
+    // isn't ASCII.
+    pub fn send_line(&mut self, line: &str) -> Result<(), NotAscii> {
+        if let Some(c) = line.chars().find(|c| !c.is_ascii()) {
+            return Err(NotAscii(c));
+        }
+
+        self.amp.extend_input(line.bytes().map(i64::from));
+        self.amp.push_input(i64::from(b'\n'));
+
+        Ok(())
+    }
+
+    pub fn next_event(&mut self) -> Result<Event, IntcodeError> {
+        if let Some(value) = self.pending.take() {
+            return Ok(Event::Value(value));
+        }
+
+        loop {
+            let event = match self.amp.resume_fast()? {
+                RunState::Output(10) => return Ok(Event::Line(self.partial.split_off(0))),
+                RunState::Output(value @ 0..=127) => {
+                    self.partial.push(value as u8 as char);
+                    continue;
+                }
+                RunState::Output(value) => Event::Value(value),
+                RunState::NeedsInput => Event::NeedsInput,
+                RunState::Halted => Event::Halted,
+            };
+
+            if self.partial.is_empty() {
+                return Ok(event);
+            }
+
+            // Input requests and halts come round again on the next call by
+            // themselves; a value has to be held back.
+            if let Event::Value(value) = event {
+                self.pending = Some(value);
+            }
+            return Ok(Event::Line(self.partial.split_off(0)));
+        }
+    }
+
+    // Every event up to and including the next input request or halt.
+    pub fn drain(&mut self) -> Result<Vec<Event>, IntcodeError> {
+        let mut events = Vec::new();
+
+        loop {
+            let event = self.next_event()?;
+            let done = matches!(event, Event::NeedsInput | Event::Halted);
+            events.push(event);
+
+            if done {
+                return Ok(events);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ascii::{Ascii, Event, NotAscii};
+    use crate::asm::assemble;
+    use crate::Amp;
+
+    #[test]
+    fn decodes_lines_and_values() {
+        // Prints "hi\n", then 1000, then "ok" without a newline.
+        let program = vec![
+            104, 104, 104, 105, 104, 10, 104, 1000, 104, 111, 104, 107, 99,
+        ];
+        let expected = vec![
+            Event::Line("hi".to_string()),
+            Event::Value(1000),
+            Event::Line("ok".to_string()),
+            Event::Halted,
+        ];
+
+        let actual = Ascii::new(Amp::new(program)).drain().unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn submits_lines_of_input() {
+        // Echoes characters back until it reads a newline, which it keeps.
+        let program = assemble(
+            "
+            loop:   in -> [c]
+                    out [c]
+                    eq [c], #10 -> [done]
+                    jf [done], #loop
+                    hlt
+            c:      .data 0
+            done:   .data 0
+            ",
+        )
+        .unwrap();
+        let mut ascii = Ascii::new(Amp::new(program));
+
+        assert_eq!(ascii.next_event(), Ok(Event::NeedsInput));
+        assert_eq!(ascii.send_line("héllo"), Err(NotAscii('é')));
+        ascii.send_line("hello").unwrap();
+
+        assert_eq!(
+            ascii.drain(),
+            Ok(vec![Event::Line("hello".to_string()), Event::Halted])
+        );
+    }
+}
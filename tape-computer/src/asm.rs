@@ -0,0 +1,415 @@
+use crate::image::Image;
+use crate::{Mode, Opcode};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum AsmErrorKind {
+    UnknownMnemonic(String),
+    UnknownDirective(String),
+    OperandCount { expected: usize, found: usize },
+    InvalidOperand(String),
+    ImmediateTarget,
+    DuplicateLabel(String),
+    UndefinedLabel(String),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct AsmError {
+    pub line: usize,
+    pub kind: AsmErrorKind,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+
+        match &self.kind {
+            AsmErrorKind::UnknownMnemonic(name) => write!(f, "unknown mnemonic `{}`", name),
+            AsmErrorKind::UnknownDirective(name) => write!(f, "unknown directive `{}`", name),
+            AsmErrorKind::OperandCount { expected, found } => {
+                write!(f, "expected {} operands, found {}", expected, found)
+            }
+            AsmErrorKind::InvalidOperand(operand) => write!(f, "invalid operand `{}`", operand),
+            AsmErrorKind::ImmediateTarget => write!(f, "write target cannot be immediate"),
+            AsmErrorKind::DuplicateLabel(label) => write!(f, "label `{}` already defined", label),
+            AsmErrorKind::UndefinedLabel(label) => write!(f, "undefined label `{}`", label),
+        }
+    }
+}
+
+impl Error for AsmError {}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Number(i64),
+    Label(String),
+}
+
+#[derive(Debug, Clone)]
+struct Operand {
+    mode: Mode,
+    value: Value,
+}
+
+#[derive(Debug)]
+enum Statement {
+    Instruction {
+        mnemonic: String,
+        operands: Vec<Operand>,
+    },
+    Data(Vec<Value>),
+    Entry(Value),
+}
+
+impl Statement {
+    fn len(&self) -> usize {
+        match self {
+            Statement::Instruction { operands, .. } => operands.len() + 1,
+            Statement::Data(values) => values.len(),
+            Statement::Entry(_) => 0,
+        }
+    }
+}
+
+pub fn assemble(source: &str) -> Result<Vec<i64>, AsmError> {
+    assemble_image(source).map(|image| image.words)
+}
+
+pub fn assemble_with_labels(source: &str) -> Result<(Vec<i64>, HashMap<String, usize>), AsmError> {
+    let image = assemble_image(source)?;
+
+    Ok((image.words, image.symbols.into_iter().collect()))
+}
+
+// Keeps labels as symbols and `;` comments as annotations on the address of
+// the next word, so the result can be saved as an annotated image.
+pub fn assemble_image(source: &str) -> Result<Image, AsmError> {
+    let mut image = Image::default();
+    let mut statements = Vec::new();
+    let mut addr = 0;
+
+    for (i, text) in source.lines().enumerate() {
+        let line = i + 1;
+        let error = |kind| AsmError { line, kind };
+
+        let (defined, statement) = parse_line(text).map_err(error)?;
+        for label in defined {
+            if image.symbols.insert(label.clone(), addr).is_some() {
+                return Err(error(AsmErrorKind::DuplicateLabel(label)));
+            }
+        }
+
+        if let Some(comment) = text.find(';').map(|start| text[start + 1..].trim()) {
+            image
+                .comments
+                .entry(addr)
+                .and_modify(|comments: &mut String| {
+                    comments.push('\n');
+                    comments.push_str(comment);
+                })
+                .or_insert_with(|| comment.to_string());
+        }
+
+        if let Some(statement) = statement {
+            addr += statement.len();
+            statements.push((line, statement));
+        }
+    }
+
+    let symbols = &image.symbols;
+    let mut program = Vec::with_capacity(addr);
+    let mut entry = 0;
+    for (line, statement) in statements {
+        let resolve = |value: &Value| match value {
+            Value::Number(n) => Ok(*n),
+            Value::Label(label) => symbols.get(label).map(|&addr| addr as i64).ok_or(AsmError {
+                line,
+                kind: AsmErrorKind::UndefinedLabel(label.clone()),
+            }),
+        };
+
+        match statement {
+            Statement::Instruction { mnemonic, operands } => {
+                let modes = operands.iter().map(|op| op.mode).collect::<Vec<_>>();
+                let opcode = opcode(&mnemonic, &modes).map_err(|kind| AsmError { line, kind })?;
+
+                program.push(opcode.encode());
+                for operand in &operands {
+                    program.push(resolve(&operand.value)?);
+                }
+            }
+            Statement::Data(values) => {
+                for value in &values {
+                    program.push(resolve(value)?);
+                }
+            }
+            Statement::Entry(value) => {
+                let target = resolve(&value)?;
+                if target < 0 {
+                    return Err(AsmError {
+                        line,
+                        kind: AsmErrorKind::InvalidOperand(target.to_string()),
+                    });
+                }
+
+                entry = target as usize;
+            }
+        }
+    }
+
+    image.words = program;
+    image.entry = entry;
+
+    Ok(image)
+}
+
+fn parse_line(text: &str) -> Result<(Vec<String>, Option<Statement>), AsmErrorKind> {
+    let mut rest = text.split(';').next().unwrap_or("").trim();
+    let mut labels = Vec::new();
+
+    // Listings from the disassembler prefix each line with its address.
+    if let Some((prefix, tail)) = split_label(rest) {
+        if prefix.chars().all(|c| c.is_ascii_digit()) {
+            rest = tail;
+        }
+    }
+
+    while let Some((label, tail)) = split_label(rest) {
+        if !is_identifier(label) {
+            break;
+        }
+
+        labels.push(label.to_string());
+        rest = tail;
+    }
+
+    if rest.is_empty() {
+        return Ok((labels, None));
+    }
+
+    let (head, operands) = match rest.find(char::is_whitespace) {
+        Some(i) => (&rest[..i], rest[i..].trim()),
+        None => (rest, ""),
+    };
+
+    let statement = if head.eq_ignore_ascii_case(".entry") {
+        Statement::Entry(parse_value(operands)?)
+    } else if head.starts_with('.') {
+        if !head.eq_ignore_ascii_case(".data") {
+            return Err(AsmErrorKind::UnknownDirective(head.to_string()));
+        }
+
+        let values = operands
+            .split(',')
+            .map(|value| parse_value(value.trim()))
+            .collect::<Result<_, _>>()?;
+        Statement::Data(values)
+    } else {
+        let operands = operands
+            .replace("->", ",")
+            .split(',')
+            .map(str::trim)
+            .filter(|operand| !operand.is_empty())
+            .map(parse_operand)
+            .collect::<Result<_, _>>()?;
+        Statement::Instruction {
+            mnemonic: head.to_lowercase(),
+            operands,
+        }
+    };
+
+    Ok((labels, Some(statement)))
+}
+
+fn split_label(text: &str) -> Option<(&str, &str)> {
+    let colon = text.find(':')?;
+    let label = text[..colon].trim();
+
+    if label.is_empty() || label.contains(char::is_whitespace) {
+        return None;
+    }
+
+    Some((label, text[colon + 1..].trim()))
+}
+
+fn is_identifier(text: &str) -> bool {
+    let mut chars = text.chars();
+
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn parse_value(text: &str) -> Result<Value, AsmErrorKind> {
+    if let Ok(number) = text.parse() {
+        Ok(Value::Number(number))
+    } else if is_identifier(text) {
+        Ok(Value::Label(text.to_string()))
+    } else {
+        Err(AsmErrorKind::InvalidOperand(text.to_string()))
+    }
+}
+
+fn parse_operand(text: &str) -> Result<Operand, AsmErrorKind> {
+    let invalid = || AsmErrorKind::InvalidOperand(text.to_string());
+
+    if let Some(value) = text.strip_prefix('#') {
+        return Ok(Operand {
+            mode: Mode::Immediate,
+            value: parse_value(value.trim())?,
+        });
+    }
+
+    let inner = text
+        .strip_prefix('[')
+        .and_then(|text| text.strip_suffix(']'))
+        .map(str::trim)
+        .ok_or_else(invalid)?;
+
+    let compact = inner.replace(' ', "");
+    if compact == "rb" || compact.starts_with("rb+") || compact.starts_with("rb-") {
+        let offset = match compact[2..].strip_prefix('+') {
+            _ if compact.len() == 2 => 0,
+            Some(positive) => positive.parse().map_err(|_| invalid())?,
+            None => compact[2..].parse().map_err(|_| invalid())?,
+        };
+
+        return Ok(Operand {
+            mode: Mode::Relative,
+            value: Value::Number(offset),
+        });
+    }
+
+    Ok(Operand {
+        mode: Mode::Position,
+        value: parse_value(inner)?,
+    })
+}
+
+fn opcode(mnemonic: &str, modes: &[Mode]) -> Result<Opcode, AsmErrorKind> {
+    let arity = match mnemonic {
+        "add" | "mul" | "lt" | "eq" => 3,
+        "jt" | "jf" => 2,
+        "in" | "out" | "arb" => 1,
+        "hlt" => 0,
+        _ => return Err(AsmErrorKind::UnknownMnemonic(mnemonic.to_string())),
+    };
+
+    if modes.len() != arity {
+        return Err(AsmErrorKind::OperandCount {
+            expected: arity,
+            found: modes.len(),
+        });
+    }
+
+    let opcode = match mnemonic {
+        "add" => Opcode::Add(modes[0], modes[1], modes[2]),
+        "mul" => Opcode::Mul(modes[0], modes[1], modes[2]),
+        "lt" => Opcode::LT(modes[0], modes[1], modes[2]),
+        "eq" => Opcode::EQ(modes[0], modes[1], modes[2]),
+        "jt" => Opcode::JIT(modes[0], modes[1]),
+        "jf" => Opcode::JIF(modes[0], modes[1]),
+        "in" => Opcode::Save(modes[0]),
+        "out" => Opcode::Output(modes[0]),
+        "arb" => Opcode::AdjustRelative(modes[0]),
+        _ => Opcode::Halt,
+    };
+
+    if opcode.writes() && modes.last() == Some(&Mode::Immediate) {
+        return Err(AsmErrorKind::ImmediateTarget);
+    }
+
+    Ok(opcode)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::asm::{assemble, AsmError, AsmErrorKind};
+    use crate::disasm::disassemble;
+    use crate::Amp;
+
+    #[test]
+    fn encodes_modes_and_labels() {
+        let source = "
+            ; count down from the input, printing each value
+            start:  in -> [counter]
+            loop:   out [counter]
+                    add [counter], #-1 -> [counter]
+                    jt [counter], #loop
+                    hlt
+            counter: .data 0
+        ";
+        let expected = vec![3, 12, 4, 12, 1001, 12, -1, 12, 1005, 12, 2, 99, 0];
+
+        let actual = assemble(source).unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn assembled_program_runs() {
+        let source = "
+                arb #100
+                in -> [rb+0]
+                mul [rb+0], #3 -> [rb-1]
+                out [rb-1]
+                hlt
+        ";
+
+        let mut amp = Amp::new(assemble(source).unwrap());
+        amp.push_input(14);
+
+        assert_eq!(amp.outputs().collect::<Vec<_>>(), vec![42]);
+    }
+
+    #[test]
+    fn reassembles_disassembly() {
+        let program = vec![
+            109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+        ];
+
+        let listing = disassemble(&program, 0).to_string();
+        let actual = assemble(&listing).unwrap();
+
+        assert_eq!(actual, program);
+    }
+
+    #[test]
+    fn reports_errors_with_line_numbers() {
+        let cases = vec![
+            (
+                "hlt\nfoo #1",
+                AsmErrorKind::UnknownMnemonic("foo".to_string()),
+            ),
+            (
+                "add #1, #2",
+                AsmErrorKind::OperandCount {
+                    expected: 3,
+                    found: 2,
+                },
+            ),
+            ("hlt\nadd #1, #2 -> #3", AsmErrorKind::ImmediateTarget),
+            (
+                "jt #1, #nowhere",
+                AsmErrorKind::UndefinedLabel("nowhere".to_string()),
+            ),
+            (
+                "a: hlt\na: hlt",
+                AsmErrorKind::DuplicateLabel("a".to_string()),
+            ),
+            (
+                "out [rb+x]",
+                AsmErrorKind::InvalidOperand("[rb+x]".to_string()),
+            ),
+        ];
+
+        for (source, kind) in cases {
+            let line = source.lines().count();
+
+            assert_eq!(assemble(source), Err(AsmError { line, kind }));
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use crate::word::WordAmp;
+use num_bigint::BigInt;
+
+// An Intcode machine whose words are arbitrary-precision integers, for
+// programs whose arithmetic outgrows i64.
+pub type BigAmp = WordAmp<BigInt>;
+
+#[cfg(test)]
+mod test {
+    use crate::bigint::BigAmp;
+    use crate::{IntcodeError, RunState};
+    use num_bigint::BigInt;
+
+    #[test]
+    fn multiplies_past_i64() {
+        // Squares its input twice.
+        let program = vec![3, 13, 2, 13, 13, 13, 2, 13, 13, 13, 4, 13, 99, 0];
+        let word = BigInt::from(1u64 << 32);
+        let expected = &word * &word * &word * &word;
+
+        let mut amp = BigAmp::new(program);
+        amp.push_input(1i64 << 32);
+
+        assert_eq!(amp.resume(), Ok(RunState::Output(expected)));
+        assert_eq!(amp.resume(), Ok(RunState::Halted));
+    }
+
+    #[test]
+    fn compares_and_jumps_on_big_words() {
+        // Prints 1 if the input is less than the huge constant, 0 otherwise.
+        let huge = BigInt::from(i64::MAX) * BigInt::from(1000);
+        let program = vec![
+            BigInt::from(3),
+            BigInt::from(9),
+            BigInt::from(1007),
+            BigInt::from(9),
+            huge.clone(),
+            BigInt::from(9),
+            BigInt::from(4),
+            BigInt::from(9),
+            BigInt::from(99),
+            BigInt::from(0),
+        ];
+
+        let mut amp = BigAmp::new(program.clone());
+        amp.push_input(&huge - BigInt::from(1));
+        assert_eq!(amp.resume(), Ok(RunState::Output(BigInt::from(1))));
+
+        let mut amp = BigAmp::new(program);
+        amp.push_input(huge);
+        assert_eq!(amp.resume(), Ok(RunState::Output(BigInt::from(0))));
+    }
+
+    #[test]
+    fn runs_code_written_past_the_program() {
+        // Writes a halt at 100 and jumps to it, then jumps past it.
+        let mut amp = BigAmp::new(vec![1101, 99, 0, 100, 1105, 1, 100]);
+        assert_eq!(amp.resume(), Ok(RunState::Halted));
+
+        let mut amp = BigAmp::new(vec![1101, 99, 0, 100, 1105, 1, 101]);
+        assert_eq!(
+            amp.resume(),
+            Err(IntcodeError::PcOutOfRange {
+                pc: 101,
+                target: 101
+            })
+        );
+    }
+}
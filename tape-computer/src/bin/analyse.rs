@@ -0,0 +1,35 @@
+use std::env;
+use std::process;
+use tape_computer::analysis::Analysis;
+use tape_computer::load;
+
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    let dot = args.iter().any(|arg| arg == "--dot");
+    let positional = args[1..]
+        .iter()
+        .filter(|arg| *arg != "--dot")
+        .collect::<Vec<_>>();
+    if positional.is_empty() {
+        eprintln!("usage: {} [--dot] <program> [entry]", args[0]);
+        process::exit(1);
+    }
+
+    let program = load::from_path(positional[0]).unwrap_or_else(|error| {
+        eprintln!("unable to load {}: {}", positional[0], error);
+        process::exit(1);
+    });
+    let entry = positional.get(1).map_or(0, |s| {
+        s.parse().unwrap_or_else(|_| {
+            eprintln!("invalid entry `{}`", s);
+            process::exit(1);
+        })
+    });
+
+    let analysis = Analysis::new(&program, entry);
+    if dot {
+        print!("{}", analysis.to_dot());
+    } else {
+        print!("{}", analysis);
+    }
+}
@@ -0,0 +1,45 @@
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::process;
+use tape_computer::ascii::{Ascii, Event};
+use tape_computer::{load, Amp};
+
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    if args.len() < 2 {
+        eprintln!("usage: {} <program>", args[0]);
+        process::exit(1);
+    }
+
+    let program = load::from_path(&args[1]).unwrap_or_else(|error| {
+        eprintln!("unable to load {}: {}", args[1], error);
+        process::exit(1);
+    });
+    let mut ascii = Ascii::new(Amp::new(program));
+    let mut lines = io::stdin().lock().lines();
+
+    loop {
+        match ascii.next_event() {
+            Ok(Event::Line(line)) => println!("{}", line),
+            Ok(Event::Value(value)) => println!("[value {}]", value),
+            Ok(Event::NeedsInput) => {
+                print!("> ");
+                io::stdout().flush().unwrap();
+
+                match lines.next() {
+                    Some(Ok(line)) => {
+                        if let Err(error) = ascii.send_line(&line) {
+                            eprintln!("{}", error);
+                        }
+                    }
+                    _ => return,
+                }
+            }
+            Ok(Event::Halted) => return,
+            Err(error) => {
+                eprintln!("{}", error);
+                process::exit(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use std::convert::TryFrom;
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::process;
+use tape_computer::debugger::{Breakpoint, Debugger, Stop};
+use tape_computer::disasm::{decode_at, Item};
+use tape_computer::load;
+use tape_computer::Amp;
+
+const HELP: &str = "\
+commands:
+  s, step [n]          execute n instructions (default 1)
+  c, continue          run until a breakpoint, watchpoint, input request or halt
+  o, output            run until the next output
+  b, break <addr>      break when pc reaches addr
+  b, break op <name>   break before any instruction with mnemonic name
+  w, watch <addr>      stop when the value at addr changes
+  d, delete [n]        delete breakpoint n, or all breakpoints and watchpoints
+  i, input <v>...      queue input values
+  r, regs              show pc, relative base and pending input
+  m, mem <addr> [n]    dump n words of memory starting at addr
+  l, list [addr] [n]   disassemble n instructions starting at addr (default pc)
+  info                 list breakpoints and watchpoints
+  q, quit              exit";
+
+// The most words `mem` dumps or instructions `list` shows at once.
+const MAX_COUNT: usize = 4096;
+
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    if args.len() < 2 {
+        eprintln!("usage: {} <program>", args[0]);
+        process::exit(1);
+    }
+
+    let program = load::from_path(&args[1]).unwrap_or_else(|error| {
+        eprintln!("unable to load {}: {}", args[1], error);
+        process::exit(1);
+    });
+
+    let mut debugger = Debugger::new(Amp::new(program));
+    let stdin = io::stdin();
+
+    list(&debugger, debugger.amp().pc(), 1);
+    loop {
+        print!("(intdbg) ");
+        io::stdout().flush().unwrap();
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap() == 0 {
+            break;
+        }
+
+        let words = line.split_whitespace().collect::<Vec<_>>();
+        match execute(&mut debugger, &words) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(message) => println!("{}", message),
+        }
+    }
+}
+
+fn execute(debugger: &mut Debugger, words: &[&str]) -> Result<bool, String> {
+    let number = |index: usize, default: Option<i64>| -> Result<i64, String> {
+        match words.get(index) {
+            Some(word) => word.parse().map_err(|_| format!("not a number: {}", word)),
+            None => default.ok_or_else(|| "missing argument".to_string()),
+        }
+    };
+    let unsigned = |index: usize, default: Option<usize>| -> Result<usize, String> {
+        let value = number(index, default.map(|value| value as i64))?;
+        usize::try_from(value).map_err(|_| format!("not an address or count: {}", value))
+    };
+
+    match words.first().cloned().unwrap_or("") {
+        "" => {}
+        "s" | "step" => {
+            for _ in 0..number(1, Some(1))? {
+                let stop = debugger.step();
+                if stop != Stop::Stepped {
+                    report(debugger, stop);
+                    return Ok(true);
+                }
+            }
+            report(debugger, Stop::Stepped);
+        }
+        "c" | "continue" => {
+            let stop = debugger.cont();
+            report(debugger, stop);
+        }
+        "o" | "output" => {
+            let stop = debugger.run_to_output();
+            report(debugger, stop);
+        }
+        "b" | "break" => {
+            let breakpoint = if words.get(1) == Some(&"op") {
+                let name = words.get(2).ok_or("missing mnemonic")?;
+                Breakpoint::Opcode(name.to_lowercase())
+            } else {
+                Breakpoint::Address(unsigned(1, None)?)
+            };
+
+            let index = debugger.add_breakpoint(breakpoint);
+            println!("breakpoint {} set", index);
+        }
+        "w" | "watch" => {
+            let addr = unsigned(1, None)?;
+            debugger.add_watchpoint(addr);
+            println!(
+                "watching [{}] = {}",
+                addr,
+                debugger.amp().memory().get(addr)
+            );
+        }
+        "d" | "delete" => match words.get(1) {
+            Some(_) => {
+                let index = unsigned(1, None)?;
+                debugger
+                    .remove_breakpoint(index)
+                    .ok_or_else(|| format!("no breakpoint {}", index))?;
+            }
+            None => {
+                while debugger.remove_breakpoint(0).is_some() {}
+                for addr in debugger.watchpoints().collect::<Vec<_>>() {
+                    debugger.remove_watchpoint(addr);
+                }
+            }
+        },
+        "i" | "input" => {
+            for index in 1..words.len() {
+                let value = number(index, None)?;
+                debugger.amp_mut().push_input(value);
+            }
+        }
+        "r" | "regs" => {
+            let amp = debugger.amp();
+            println!(
+                "pc = {}  rb = {}  input = {:?}",
+                amp.pc(),
+                amp.relative_base(),
+                amp.pending_input()
+            );
+        }
+        "m" | "mem" => {
+            let start = unsigned(1, None)?;
+            let count = unsigned(2, Some(8))?.min(MAX_COUNT);
+            let end = start.checked_add(count).ok_or("address out of range")?;
+            let memory = debugger.amp().memory();
+
+            for row in (start..end).collect::<Vec<_>>().chunks(8) {
+                let values = row.iter().map(|&addr| format!("{:>8}", memory.get(addr)));
+                println!("{:04}: {}", row[0], values.collect::<Vec<_>>().join(" "));
+            }
+        }
+        "l" | "list" => {
+            let start = unsigned(1, Some(debugger.amp().pc()))?;
+            let count = unsigned(2, Some(10))?.min(MAX_COUNT);
+            list(debugger, start, count);
+        }
+        "info" => {
+            for (index, breakpoint) in debugger.breakpoints().iter().enumerate() {
+                match breakpoint {
+                    Breakpoint::Address(addr) => println!("breakpoint {} at {}", index, addr),
+                    Breakpoint::Opcode(name) => println!("breakpoint {} on {}", index, name),
+                }
+            }
+            for addr in debugger.watchpoints() {
+                println!("watchpoint on [{}]", addr);
+            }
+        }
+        "h" | "help" => println!("{}", HELP),
+        "q" | "quit" => return Ok(false),
+        command => return Err(format!("unknown command: {} (try `help`)", command)),
+    }
+
+    Ok(true)
+}
+
+fn report(debugger: &mut Debugger, stop: Stop) {
+    for value in debugger.take_outputs() {
+        println!("output: {}", value);
+    }
+
+    match stop {
+        Stop::Stepped => {}
+        Stop::Breakpoint(index) => println!("hit breakpoint {}", index),
+        Stop::Watchpoint { addr, old, new } => println!("[{}] changed: {} -> {}", addr, old, new),
+        Stop::Output(value) => println!("output: {}", value),
+        Stop::NeedsInput => println!("program needs input (use `input <value>`)"),
+        Stop::Halted => println!("program halted"),
+        Stop::Fault(error) => println!("fault: {}", error),
+    }
+
+    list(debugger, debugger.amp().pc(), 1);
+}
+
+fn list(debugger: &Debugger, start: usize, count: usize) {
+    let memory = debugger.amp().memory();
+    let mut addr = start;
+
+    for _ in 0..count {
+        let window = (addr..addr.saturating_add(4))
+            .map(|a| memory.get(a))
+            .collect::<Vec<_>>();
+        let marker = if addr == debugger.amp().pc() {
+            "=>"
+        } else {
+            "  "
+        };
+
+        let width = match decode_at(&window, 0) {
+            Some((opcode, params)) => {
+                let item = Item::Instruction { opcode, params };
+                println!("{} {:04}: {}", marker, addr, item);
+                usize::from(opcode).max(1)
+            }
+            None => {
+                println!("{} {:04}: {}", marker, addr, Item::Data(memory.get(addr)));
+                1
+            }
+        };
+
+        addr = match addr.checked_add(width) {
+            Some(next) => next,
+            None => break,
+        };
+    }
+}
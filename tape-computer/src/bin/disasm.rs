@@ -0,0 +1,25 @@
+use std::env;
+use std::process;
+use tape_computer::disasm::disassemble;
+use tape_computer::load;
+
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    if args.len() < 2 {
+        eprintln!("usage: {} <program> [start]", args[0]);
+        process::exit(1);
+    }
+
+    let program = load::from_path(&args[1]).unwrap_or_else(|error| {
+        eprintln!("unable to load {}: {}", args[1], error);
+        process::exit(1);
+    });
+    let start = args.get(2).map_or(0, |s| {
+        s.parse().unwrap_or_else(|_| {
+            eprintln!("invalid start `{}`", s);
+            process::exit(1);
+        })
+    });
+
+    print!("{}", disassemble(&program, start));
+}
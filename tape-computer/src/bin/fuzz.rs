@@ -0,0 +1,27 @@
+use std::env;
+use std::process;
+use tape_computer::fuzz::Fuzzer;
+
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    let cases = args.get(1).map_or(10_000, |s| {
+        s.parse().unwrap_or_else(|_| {
+            eprintln!("invalid case count `{}`", s);
+            process::exit(1);
+        })
+    });
+    let seed = args.get(2).map_or(2019, |s| {
+        s.parse().unwrap_or_else(|_| {
+            eprintln!("invalid seed `{}`", s);
+            process::exit(1);
+        })
+    });
+
+    match Fuzzer::new(seed).run(cases) {
+        Some(divergence) => {
+            print!("{}", divergence);
+            process::exit(1);
+        }
+        None => println!("no divergence in {} cases", cases),
+    }
+}
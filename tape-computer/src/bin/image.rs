@@ -0,0 +1,53 @@
+use std::env;
+use std::fs;
+use std::process;
+use tape_computer::asm::assemble_image;
+use tape_computer::image::Image;
+use tape_computer::load;
+
+fn usage(name: &str) -> ! {
+    eprintln!("usage: {} pack <program|source.asm> <image>", name);
+    eprintln!("       {} unpack <image>", name);
+    eprintln!("       {} list <image>", name);
+    process::exit(1);
+}
+
+fn fail<E: std::fmt::Display>(path: &str, error: E) -> ! {
+    eprintln!("{}: {}", path, error);
+    process::exit(1);
+}
+
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    if args.len() < 3 {
+        usage(&args[0]);
+    }
+
+    let path = args[2].as_str();
+    match args[1].as_str() {
+        "pack" if args.len() == 4 => {
+            let image = if path.ends_with(".asm") {
+                let source = fs::read_to_string(path).unwrap_or_else(|error| fail(path, error));
+                assemble_image(&source).unwrap_or_else(|error| fail(path, error))
+            } else {
+                Image::new(load::from_path(path).unwrap_or_else(|error| fail(path, error)))
+            };
+
+            image
+                .save(&args[3])
+                .unwrap_or_else(|error| fail(&args[3], error));
+        }
+        "unpack" => {
+            let image = Image::load(path).unwrap_or_else(|error| fail(path, error));
+            let text = image
+                .to_text()
+                .unwrap_or_else(|error| fail(path, format!("{}; use `list` to keep them", error)));
+            println!("{}", text);
+        }
+        "list" => {
+            let image = Image::load(path).unwrap_or_else(|error| fail(path, error));
+            print!("{}", image.listing());
+        }
+        _ => usage(&args[0]),
+    }
+}
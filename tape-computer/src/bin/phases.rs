@@ -0,0 +1,61 @@
+use std::env;
+use std::process;
+use tape_computer::load;
+use tape_computer::phase::{search_with_progress, Wiring};
+
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    if args.len() < 5 {
+        eprintln!(
+            "usage: {} <program> <serial|feedback> <length> <setting...>",
+            args[0]
+        );
+        process::exit(1);
+    }
+
+    let program = load::from_path(&args[1]).unwrap_or_else(|error| {
+        eprintln!("unable to load {}: {}", args[1], error);
+        process::exit(1);
+    });
+
+    let wiring = match args[2].as_str() {
+        "serial" => Wiring::Serial,
+        "feedback" => Wiring::Feedback,
+        other => {
+            eprintln!("unknown wiring `{}`", other);
+            process::exit(1);
+        }
+    };
+    let length = args[3].parse().unwrap_or_else(|_| {
+        eprintln!("invalid chain length `{}`", args[3]);
+        process::exit(1);
+    });
+    let settings = args[4..]
+        .iter()
+        .map(|arg| {
+            arg.parse().unwrap_or_else(|_| {
+                eprintln!("invalid phase setting `{}`", arg);
+                process::exit(1);
+            })
+        })
+        .collect::<Vec<i64>>();
+
+    let result = search_with_progress(&program, &settings, length, wiring, |progress| {
+        if progress.evaluated % 1000 == 0 || Some(progress.evaluated) == progress.total {
+            match progress.total {
+                Some(total) => eprint!("\r{}/{} orderings", progress.evaluated, total),
+                None => eprint!("\r{} orderings", progress.evaluated),
+            }
+        }
+    });
+    eprintln!();
+
+    match result {
+        Ok(Some(best)) => println!("{:?} -> {}", best.phases, best.signal),
+        Ok(None) => println!("no ordering produced a signal"),
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(1);
+        }
+    }
+}
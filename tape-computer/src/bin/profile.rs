@@ -0,0 +1,54 @@
+use std::env;
+use std::process;
+use tape_computer::disasm::{decode_at, Item};
+use tape_computer::load;
+use tape_computer::trace::Profile;
+use tape_computer::Amp;
+
+fn main() {
+    let args = env::args().collect::<Vec<_>>();
+    if args.len() < 2 {
+        eprintln!("usage: {} <program> [input...]", args[0]);
+        process::exit(1);
+    }
+
+    let program = load::from_path(&args[1]).unwrap_or_else(|error| {
+        eprintln!("unable to load {}: {}", args[1], error);
+        process::exit(1);
+    });
+
+    let mut amp = Amp::new(program.clone());
+    amp.extend_input(args[2..].iter().map(|arg| {
+        arg.parse().unwrap_or_else(|_| {
+            eprintln!("invalid input `{}`", arg);
+            process::exit(1);
+        })
+    }));
+
+    let mut profile = Profile::new();
+    let mut outputs = Vec::new();
+    let state = loop {
+        match amp.resume_traced(&mut profile) {
+            Ok(tape_computer::RunState::Output(value)) => outputs.push(value),
+            state => break state,
+        }
+    };
+
+    println!("stopped: {:?}", state);
+    println!("outputs: {:?}", outputs);
+    println!("instructions executed: {}", profile.total);
+
+    println!("\nby opcode:");
+    for (name, hits) in profile.opcodes() {
+        println!("  {:<4}{:>12}", name, hits);
+    }
+
+    println!("\nhottest addresses:");
+    for (addr, hits) in profile.hottest_addresses(20) {
+        let text = match decode_at(&program, addr) {
+            Some((opcode, params)) => Item::Instruction { opcode, params }.to_string(),
+            None => "(modified code)".to_string(),
+        };
+        println!("  {:04}{:>12}  {}", addr, hits, text);
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{Amp, IntcodeError, RunState};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Breakpoint {
+    Address(usize),
+    Opcode(String),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Stop {
+    Stepped,
+    Breakpoint(usize),
+    Watchpoint { addr: usize, old: i64, new: i64 },
+    Output(i64),
+    NeedsInput,
+    Halted,
+    Fault(IntcodeError),
+}
+
+#[derive(Debug, Clone)]
+pub struct Debugger {
+    amp: Amp,
+    breakpoints: Vec<Breakpoint>,
+    watchpoints: Vec<(usize, i64)>,
+    outputs: Vec<i64>,
+}
+
+impl Debugger {
+    pub fn new(amp: Amp) -> Self {
+        Self {
+            amp,
+            breakpoints: Vec::new(),
+            watchpoints: Vec::new(),
+            outputs: Vec::new(),
+        }
+    }
+
+    pub fn amp(&self) -> &Amp {
+        &self.amp
+    }
+
+    pub fn amp_mut(&mut self) -> &mut Amp {
+        &mut self.amp
+    }
+
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        self.breakpoints.push(breakpoint);
+        self.breakpoints.len() - 1
+    }
+
+    pub fn remove_breakpoint(&mut self, index: usize) -> Option<Breakpoint> {
+        if index < self.breakpoints.len() {
+            Some(self.breakpoints.remove(index))
+        } else {
+            None
+        }
+    }
+
+    pub fn watchpoints(&self) -> impl Iterator<Item = usize> + '_ {
+        self.watchpoints.iter().map(|&(addr, _)| addr)
+    }
+
+    pub fn add_watchpoint(&mut self, addr: usize) {
+        let value = self.amp.memory().get(addr);
+        self.watchpoints.push((addr, value));
+    }
+
+    pub fn remove_watchpoint(&mut self, addr: usize) {
+        self.watchpoints.retain(|&(watched, _)| watched != addr);
+    }
+
+    // Outputs produced while continuing past them, oldest first.
+    pub fn take_outputs(&mut self) -> Vec<i64> {
+        std::mem::take(&mut self.outputs)
+    }
+
+    pub fn step(&mut self) -> Stop {
+        match self.execute() {
+            Some(stop) => stop,
+            None => self.check_watchpoints().unwrap_or(Stop::Stepped),
+        }
+    }
+
+    pub fn cont(&mut self) -> Stop {
+        self.run(false)
+    }
+
+    pub fn run_to_output(&mut self) -> Stop {
+        self.run(true)
+    }
+
+    fn run(&mut self, stop_on_output: bool) -> Stop {
+        let mut first = true;
+
+        loop {
+            // A breakpoint at the current pc must not stop us twice.
+            if !first {
+                if let Some(index) = self.hit_breakpoint() {
+                    return Stop::Breakpoint(index);
+                }
+            }
+            first = false;
+
+            match self.execute() {
+                Some(Stop::Output(value)) if !stop_on_output => self.outputs.push(value),
+                Some(stop) => return stop,
+                None => {}
+            }
+
+            if let Some(stop) = self.check_watchpoints() {
+                return stop;
+            }
+        }
+    }
+
+    fn execute(&mut self) -> Option<Stop> {
+        match self.amp.step() {
+            Ok(None) => None,
+            Ok(Some(RunState::Output(value))) => Some(Stop::Output(value)),
+            Ok(Some(RunState::NeedsInput)) => Some(Stop::NeedsInput),
+            Ok(Some(RunState::Halted)) => Some(Stop::Halted),
+            Err(error) => Some(Stop::Fault(error)),
+        }
+    }
+
+    fn hit_breakpoint(&self) -> Option<usize> {
+        let pc = self.amp.pc();
+        let instruction = self.amp.current_instruction().ok();
+        let mnemonic = instruction
+            .as_ref()
+            .map(|(instruction, _)| instruction.mnemonic());
+
+        self.breakpoints
+            .iter()
+            .position(|breakpoint| match breakpoint {
+                Breakpoint::Address(addr) => *addr == pc,
+                Breakpoint::Opcode(name) => Some(name.as_str()) == mnemonic,
+            })
+    }
+
+    fn check_watchpoints(&mut self) -> Option<Stop> {
+        let memory = self.amp.memory();
+        let mut stop = None;
+
+        for (addr, old) in self.watchpoints.iter_mut() {
+            let new = memory.get(*addr);
+
+            if new != *old && stop.is_none() {
+                stop = Some(Stop::Watchpoint {
+                    addr: *addr,
+                    old: *old,
+                    new,
+                });
+            }
+            *old = new;
+        }
+
+        stop
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::asm::assemble;
+    use crate::debugger::{Breakpoint, Debugger, Stop};
+    use crate::Amp;
+
+    fn counter() -> Debugger {
+        let program = assemble(
+            "
+            loop:   add [n], #1 -> [n]
+                    out [n]
+                    lt [n], #3 -> [flag]
+                    jt [flag], #loop
+                    hlt
+            n:      .data 0
+            flag:   .data 0
+            ",
+        )
+        .unwrap();
+
+        Debugger::new(Amp::new(program))
+    }
+
+    #[test]
+    fn stops_at_address_breakpoints_once_per_visit() {
+        let mut debugger = counter();
+        debugger.add_breakpoint(Breakpoint::Address(0));
+
+        assert_eq!(debugger.cont(), Stop::Breakpoint(0));
+        assert_eq!(debugger.take_outputs(), vec![1]);
+        assert_eq!(debugger.cont(), Stop::Breakpoint(0));
+        assert_eq!(debugger.cont(), Stop::Halted);
+        assert_eq!(debugger.take_outputs(), vec![2, 3]);
+    }
+
+    #[test]
+    fn stops_at_opcode_breakpoints() {
+        let mut debugger = counter();
+        debugger.add_breakpoint(Breakpoint::Opcode("lt".to_string()));
+
+        assert_eq!(debugger.cont(), Stop::Breakpoint(0));
+        assert_eq!(debugger.amp().pc(), 6);
+    }
+
+    #[test]
+    fn reports_watchpoint_changes() {
+        let mut debugger = counter();
+        debugger.add_watchpoint(15);
+
+        assert_eq!(debugger.step(), Stop::Stepped);
+        assert_eq!(debugger.step(), Stop::Output(1));
+        assert_eq!(
+            debugger.step(),
+            Stop::Watchpoint {
+                addr: 15,
+                old: 0,
+                new: 1
+            }
+        );
+    }
+
+    #[test]
+    fn runs_to_next_output() {
+        let mut debugger = counter();
+
+        assert_eq!(debugger.run_to_output(), Stop::Output(1));
+        assert_eq!(debugger.run_to_output(), Stop::Output(2));
+        assert!(debugger.take_outputs().is_empty());
+    }
+}
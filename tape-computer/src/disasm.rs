@@ -0,0 +1,258 @@
+use crate::{Mode, Opcode};
+use std::collections::BTreeSet;
+use std::fmt;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Item {
+    Instruction { opcode: Opcode, params: Vec<i64> },
+    Data(i64),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Line {
+    pub addr: usize,
+    pub item: Item,
+}
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Listing {
+    pub lines: Vec<Line>,
+}
+
+impl Listing {
+    pub fn code_addresses(&self) -> impl Iterator<Item = usize> + '_ {
+        self.lines.iter().filter_map(|line| match line.item {
+            Item::Instruction { .. } => Some(line.addr),
+            Item::Data(_) => None,
+        })
+    }
+}
+
+// Decodes the instruction at `addr`, or None if the word isn't a valid opcode
+// or its parameters run past the end of the program.
+pub fn decode_at(program: &[i64], addr: usize) -> Option<(Opcode, Vec<i64>)> {
+    let opcode = Opcode::decode(*program.get(addr)?, addr).ok()?;
+    let params = program.get(addr + 1..addr + 1 + opcode.modes().len())?;
+
+    Some((opcode, params.to_vec()))
+}
+
+// Static successors of an instruction: the fall-through address unless the
+// instruction halts or always jumps, plus any immediate-mode jump target.
+pub fn successors(addr: usize, opcode: Opcode, params: &[i64]) -> Vec<usize> {
+    let next = addr + opcode.modes().len() + 1;
+
+    match opcode {
+        Opcode::Halt => vec![],
+        Opcode::JIT(m1, m2) | Opcode::JIF(m1, m2) => {
+            let jumps_on_nonzero = matches!(opcode, Opcode::JIT(_, _));
+            let taken = match m1 {
+                Mode::Immediate => Some((params[0] != 0) == jumps_on_nonzero),
+                _ => None,
+            };
+
+            let mut targets = vec![];
+            if taken != Some(true) {
+                targets.push(next);
+            }
+            if taken != Some(false) && m2 == Mode::Immediate && params[1] >= 0 {
+                targets.push(params[1] as usize);
+            }
+
+            targets
+        }
+        _ => vec![next],
+    }
+}
+
+// Puzzle programs call subroutines by pushing a constant return address onto
+// the stack and jumping unconditionally; treat that constant as code too.
+pub(crate) fn return_site(
+    program: &[i64],
+    addr: usize,
+    opcode: Opcode,
+    params: &[i64],
+) -> Option<usize> {
+    let value = match opcode {
+        Opcode::Add(Mode::Immediate, Mode::Immediate, Mode::Relative) => {
+            params[0].checked_add(params[1])?
+        }
+        Opcode::Mul(Mode::Immediate, Mode::Immediate, Mode::Relative) => {
+            params[0].checked_mul(params[1])?
+        }
+        _ => return None,
+    };
+
+    let next = addr + params.len() + 1;
+    let (jump, jump_params) = decode_at(program, next)?;
+    match jump {
+        Opcode::JIT(_, _) | Opcode::JIF(_, _) => {}
+        _ => return None,
+    }
+
+    let always_taken = !successors(next, jump, &jump_params).contains(&(next + 3));
+    if always_taken && value >= 0 {
+        Some(value as usize)
+    } else {
+        None
+    }
+}
+
+pub fn disassemble(program: &[i64], start: usize) -> Listing {
+    let mut starts = BTreeSet::new();
+    let mut pending = vec![start];
+
+    while let Some(addr) = pending.pop() {
+        if starts.contains(&addr) {
+            continue;
+        }
+
+        if let Some((opcode, params)) = decode_at(program, addr) {
+            starts.insert(addr);
+            pending.extend(successors(addr, opcode, &params));
+            pending.extend(return_site(program, addr, opcode, &params));
+        }
+    }
+
+    let mut lines = Vec::new();
+    let mut addr = 0;
+    while addr < program.len() {
+        match decode_at(program, addr).filter(|_| starts.contains(&addr)) {
+            Some((opcode, params)) => {
+                let width = params.len() + 1;
+                lines.push(Line {
+                    addr,
+                    item: Item::Instruction { opcode, params },
+                });
+                addr += width;
+            }
+            None => {
+                lines.push(Line {
+                    addr,
+                    item: Item::Data(program[addr]),
+                });
+                addr += 1;
+            }
+        }
+    }
+
+    Listing { lines }
+}
+
+pub fn format_operand(mode: Mode, param: i64) -> String {
+    match mode {
+        Mode::Position => format!("[{}]", param),
+        Mode::Immediate => format!("#{}", param),
+        Mode::Relative if param < 0 => format!("[rb{}]", param),
+        Mode::Relative => format!("[rb+{}]", param),
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Item::Instruction { opcode, params } => {
+                let mut operands = opcode
+                    .modes()
+                    .into_iter()
+                    .zip(params)
+                    .map(|(mode, &param)| format_operand(mode, param))
+                    .collect::<Vec<_>>();
+                let target = if opcode.writes() {
+                    operands.pop()
+                } else {
+                    None
+                };
+
+                write!(f, "{}", opcode.mnemonic().to_uppercase())?;
+                if !operands.is_empty() {
+                    write!(f, " {}", operands.join(", "))?;
+                }
+                if let Some(target) = target {
+                    write!(f, " -> {}", target)?;
+                }
+
+                Ok(())
+            }
+            Item::Data(value) => write!(f, ".data {}", value),
+        }
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04}: {}", self.addr, self.item)
+    }
+}
+
+impl fmt::Display for Listing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in &self.lines {
+            writeln!(f, "{}", line)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::disasm::disassemble;
+
+    #[test]
+    fn formats_operand_modes() {
+        let program = vec![21101, 3, 5, 100, 99];
+        let expected = "0000: ADD #3, #5 -> [rb+100]\n0004: HLT\n";
+
+        let actual = disassemble(&program, 0).to_string();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn follows_jump_targets_and_marks_data() {
+        let program = vec![1105, 1, 5, 1234, -7, 3, 10, 204, -2, 99, 0];
+        let expected = "\
+0000: JT #1, #5
+0003: .data 1234
+0004: .data -7
+0005: IN -> [10]
+0007: OUT [rb-2]
+0009: HLT
+0010: .data 0
+";
+
+        let actual = disassemble(&program, 0).to_string();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn treats_pushed_return_address_as_code() {
+        let program = vec![21101, 0, 7, 0, 1105, 1, 8, 99, 2105, 1, 0];
+        let expected = "\
+0000: ADD #0, #7 -> [rb+0]
+0004: JT #1, #8
+0007: HLT
+0008: JT #1, [rb+0]
+";
+
+        let actual = disassemble(&program, 0).to_string();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn ignores_return_addresses_that_overflow() {
+        let program = vec![21101, i64::MAX, 1, 0, 1106, 0, 0, 99];
+        let expected = "\
+0000: ADD #9223372036854775807, #1 -> [rb+0]
+0004: JF #0, #0
+0007: .data 99
+";
+
+        let actual = disassemble(&program, 0).to_string();
+
+        assert_eq!(actual, expected);
+    }
+}
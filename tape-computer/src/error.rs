@@ -0,0 +1,37 @@
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum IntcodeError {
+    UnknownOpcode { pc: usize, opcode: i64 },
+    InvalidMode { pc: usize, mode: i64 },
+    ImmediateWrite { pc: usize },
+    NegativeAddress { pc: usize, address: i64 },
+    PcOutOfRange { pc: usize, target: i64 },
+    Overflow { pc: usize },
+}
+
+impl fmt::Display for IntcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IntcodeError::UnknownOpcode { pc, opcode } => {
+                write!(f, "unknown opcode {} at pc {}", opcode, pc)
+            }
+            IntcodeError::InvalidMode { pc, mode } => {
+                write!(f, "invalid parameter mode {} at pc {}", mode, pc)
+            }
+            IntcodeError::ImmediateWrite { pc } => {
+                write!(f, "write to immediate-mode parameter at pc {}", pc)
+            }
+            IntcodeError::NegativeAddress { pc, address } => {
+                write!(f, "negative address {} at pc {}", address, pc)
+            }
+            IntcodeError::PcOutOfRange { pc, target } => {
+                write!(f, "pc {} out of range (from pc {})", target, pc)
+            }
+            IntcodeError::Overflow { pc } => write!(f, "arithmetic overflow at pc {}", pc),
+        }
+    }
+}
+
+impl Error for IntcodeError {}
@@ -0,0 +1,262 @@
+use crate::instruction::Effect;
+use crate::word::Word;
+use crate::{IntcodeError, Memory, Mode, Opcode, Overflow, RunState};
+
+// The state the builtin instructions work on. `Amp`, `WordAmp` and
+// `Symbolic` differ in what a word is and in how they report a word that
+// has no value as a machine integer; decoding, addressing and the
+// instructions themselves are all done here, once.
+pub(crate) trait Machine {
+    type Word: Word;
+    type Error: From<IntcodeError>;
+
+    fn pc(&self) -> usize;
+    fn set_pc(&mut self, pc: usize);
+    fn overflow(&self) -> Overflow;
+    fn relative(&self) -> &Self::Word;
+    fn set_relative(&mut self, relative: Self::Word) -> Result<(), Self::Error>;
+    fn memory(&self) -> &Memory<Self::Word>;
+    fn store(&mut self, addr: usize, value: Self::Word);
+    fn input(&mut self) -> Option<Self::Word>;
+
+    // An opcode, jump condition or jump target with no machine-integer value.
+    fn unknown_control(&self) -> Self::Error {
+        IntcodeError::Overflow { pc: self.pc() }.into()
+    }
+
+    // An address with no machine-integer value.
+    fn unknown_address(&self) -> Self::Error {
+        IntcodeError::Overflow { pc: self.pc() }.into()
+    }
+
+    // What reading through such an address gives, if anything.
+    fn load_unknown(&self, _addr: Self::Word) -> Result<Self::Word, Self::Error> {
+        Err(self.unknown_address())
+    }
+}
+
+// Decodes, executes and moves past the builtin instruction at the pc.
+pub(crate) fn step<M: Machine>(machine: &mut M) -> Result<Effect<M::Word>, M::Error> {
+    check_pc(machine)?;
+
+    let word = machine.memory().get(machine.pc());
+    let word = word.to_i64().ok_or_else(|| machine.unknown_control())?;
+    let opcode = Opcode::decode(word, machine.pc())?;
+    let params = fetch(machine, usize::from(opcode).saturating_sub(1));
+
+    let effect = execute(machine, opcode, &params)?;
+    advance(machine, usize::from(opcode), &effect)?;
+
+    Ok(effect)
+}
+
+// Memory ends after the highest address written, so code written past the
+// program can still run.
+pub(crate) fn check_pc<M: Machine>(machine: &M) -> Result<(), M::Error> {
+    let pc = machine.pc();
+    if pc >= machine.memory().len() {
+        return Err(IntcodeError::PcOutOfRange {
+            pc,
+            target: pc as i64,
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
+// The first `count` parameter words after the pc; no builtin takes more
+// than three.
+pub(crate) fn fetch<M: Machine>(machine: &M, count: usize) -> [M::Word; 3] {
+    std::array::from_fn(|i| {
+        if i < count {
+            machine.memory().get(machine.pc() + 1 + i)
+        } else {
+            M::Word::default()
+        }
+    })
+}
+
+// Runs `opcode` with the given raw parameter words, leaving the pc alone.
+#[inline(always)]
+pub(crate) fn execute<M: Machine>(
+    machine: &mut M,
+    opcode: Opcode,
+    params: &[M::Word; 3],
+) -> Result<Effect<M::Word>, M::Error> {
+    let (pc, overflow) = (machine.pc(), machine.overflow());
+    let param = |i: usize| params[i].clone();
+
+    Ok(match opcode {
+        Opcode::Add(m1, m2, m3) => {
+            let (a, b) = (load(machine, param(0), m1)?, load(machine, param(1), m2)?);
+            store(machine, param(2), m3, a.add(&b, overflow, pc)?)?;
+            Effect::Next
+        }
+        Opcode::Mul(m1, m2, m3) => {
+            let (a, b) = (load(machine, param(0), m1)?, load(machine, param(1), m2)?);
+            store(machine, param(2), m3, a.mul(&b, overflow, pc)?)?;
+            Effect::Next
+        }
+        Opcode::Save(m1) => match machine.input() {
+            Some(value) => {
+                store(machine, param(0), m1, value)?;
+                Effect::Next
+            }
+            None => Effect::NeedsInput,
+        },
+        Opcode::Output(m1) => Effect::Output(load(machine, param(0), m1)?),
+        Opcode::JIT(m1, m2) | Opcode::JIF(m1, m2) => {
+            let jumps_on_nonzero = matches!(opcode, Opcode::JIT(_, _));
+            let condition = load(machine, param(0), m1)?;
+            let zero = condition
+                .is_zero()
+                .ok_or_else(|| machine.unknown_control())?;
+
+            if zero != jumps_on_nonzero {
+                Effect::Jump(load(machine, param(1), m2)?)
+            } else {
+                Effect::Next
+            }
+        }
+        Opcode::LT(m1, m2, m3) => {
+            let (a, b) = (load(machine, param(0), m1)?, load(machine, param(1), m2)?);
+            store(machine, param(2), m3, a.less(&b))?;
+            Effect::Next
+        }
+        Opcode::EQ(m1, m2, m3) => {
+            let (a, b) = (load(machine, param(0), m1)?, load(machine, param(1), m2)?);
+            store(machine, param(2), m3, a.equal(&b))?;
+            Effect::Next
+        }
+        Opcode::AdjustRelative(m1) => {
+            let offset = load(machine, param(0), m1)?;
+            let relative = machine.relative().add(&offset, overflow, pc)?;
+            machine.set_relative(relative)?;
+            Effect::Next
+        }
+        Opcode::Halt => Effect::Halt,
+    })
+}
+
+// Moves the pc on from an instruction `len` words long that had `effect`.
+#[inline(always)]
+pub(crate) fn advance<M: Machine>(
+    machine: &mut M,
+    len: usize,
+    effect: &Effect<M::Word>,
+) -> Result<(), M::Error> {
+    match effect {
+        Effect::Next | Effect::Output(_) => machine.set_pc(machine.pc() + len),
+        Effect::Jump(target) => {
+            let target = target.to_i64().ok_or_else(|| machine.unknown_control())?;
+            if target < 0 {
+                return Err(IntcodeError::PcOutOfRange {
+                    pc: machine.pc(),
+                    target,
+                }
+                .into());
+            }
+
+            machine.set_pc(target as usize);
+        }
+        Effect::NeedsInput | Effect::Halt => {}
+    }
+
+    Ok(())
+}
+
+pub(crate) fn run_state<W>(effect: Effect<W>) -> Option<RunState<W>> {
+    match effect {
+        Effect::Next | Effect::Jump(_) => None,
+        Effect::Output(value) => Some(RunState::Output(value)),
+        Effect::NeedsInput => Some(RunState::NeedsInput),
+        Effect::Halt => Some(RunState::Halted),
+    }
+}
+
+// The address the parameter at `offset` words past the pc refers to. An
+// immediate parameter is its own address.
+pub(crate) fn address<M: Machine>(
+    machine: &M,
+    offset: usize,
+    mode: Mode,
+) -> Result<usize, M::Error> {
+    if let Mode::Immediate = mode {
+        return Ok(machine.pc() + offset);
+    }
+
+    let param = machine.memory().get(machine.pc() + offset);
+    locate(machine, param, mode)?.map_err(|_| machine.unknown_address())
+}
+
+pub(crate) fn read<M: Machine>(
+    machine: &M,
+    offset: usize,
+    mode: Mode,
+) -> Result<M::Word, M::Error> {
+    load(machine, machine.memory().get(machine.pc() + offset), mode)
+}
+
+pub(crate) fn write<M: Machine>(
+    machine: &mut M,
+    offset: usize,
+    mode: Mode,
+    value: M::Word,
+) -> Result<(), M::Error> {
+    let param = machine.memory().get(machine.pc() + offset);
+    store(machine, param, mode, value)
+}
+
+#[inline(always)]
+fn load<M: Machine>(machine: &M, param: M::Word, mode: Mode) -> Result<M::Word, M::Error> {
+    if let Mode::Immediate = mode {
+        return Ok(param);
+    }
+
+    match locate(machine, param, mode)? {
+        Ok(addr) => Ok(machine.memory().get(addr)),
+        Err(addr) => machine.load_unknown(addr),
+    }
+}
+
+#[inline(always)]
+fn store<M: Machine>(
+    machine: &mut M,
+    param: M::Word,
+    mode: Mode,
+    value: M::Word,
+) -> Result<(), M::Error> {
+    if let Mode::Immediate = mode {
+        return Err(IntcodeError::ImmediateWrite { pc: machine.pc() }.into());
+    }
+
+    let addr = locate(machine, param, mode)?.map_err(|_| machine.unknown_address())?;
+    machine.store(addr, value);
+
+    Ok(())
+}
+
+// Where a position or relative parameter points, or the address word itself
+// if it has no machine-integer value.
+#[inline(always)]
+fn locate<M: Machine>(
+    machine: &M,
+    param: M::Word,
+    mode: Mode,
+) -> Result<Result<usize, M::Word>, M::Error> {
+    let addr = match mode {
+        Mode::Relative => param.add(machine.relative(), machine.overflow(), machine.pc())?,
+        _ => param,
+    };
+
+    match addr.to_i64() {
+        Some(address) if address < 0 => Err(IntcodeError::NegativeAddress {
+            pc: machine.pc(),
+            address,
+        }
+        .into()),
+        Some(address) => Ok(Ok(address as usize)),
+        None => Ok(Err(addr)),
+    }
+}
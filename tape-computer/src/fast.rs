@@ -0,0 +1,241 @@
+use crate::exec;
+use crate::{Amp, IntcodeError, Opcode, RunState};
+
+// Only instructions in the low, densely stored part of memory are cached;
+// anything else falls back to the ordinary decoder.
+const CACHE_LIMIT: usize = 1 << 16;
+
+#[derive(Copy, Clone, Debug)]
+struct Decoded {
+    opcode: Opcode,
+    params: [i64; 3],
+    len: usize,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct DecodeCache {
+    entries: Vec<Option<Decoded>>,
+}
+
+// Forks start cold rather than copying a cache that is usually larger than
+// the memory pages they share.
+impl Clone for DecodeCache {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl DecodeCache {
+    // An instruction starting up to three words before `addr` has `addr` as
+    // one of its parameters, so all of those entries are stale.
+    pub(crate) fn invalidate(&mut self, addr: usize) {
+        if addr >= self.entries.len() + 3 {
+            return;
+        }
+
+        let end = (addr + 1).min(self.entries.len());
+        for entry in &mut self.entries[addr.saturating_sub(3)..end] {
+            *entry = None;
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+impl Amp {
+    // Same semantics as `resume`, but instructions are decoded once and
+    // replayed from a cache until something writes over them.
+    pub fn resume_fast(&mut self) -> Result<RunState, IntcodeError> {
+        let mut unused = 0;
+        let state = self.run_cached::<false>(&mut unused)?;
+
+        Ok(state.expect("an unbounded run only returns when the machine stops"))
+    }
+
+    // Executes at most `budget` instructions, counting them off. Returns None
+    // if the budget runs out before the machine stops.
+    pub(crate) fn resume_fast_bounded(
+        &mut self,
+        budget: &mut usize,
+    ) -> Result<Option<RunState>, IntcodeError> {
+        self.run_cached::<true>(budget)
+    }
+
+    // The budget check compiles away entirely for `resume_fast`.
+    fn run_cached<const BOUNDED: bool>(
+        &mut self,
+        budget: &mut usize,
+    ) -> Result<Option<RunState>, IntcodeError> {
+        loop {
+            if BOUNDED {
+                if *budget == 0 {
+                    return Ok(None);
+                }
+                *budget -= 1;
+            }
+
+            let decoded = match self.decoded_at(self.pc) {
+                Some(decoded) => decoded,
+                None => match self.step()? {
+                    Some(state) => return Ok(Some(state)),
+                    None => continue,
+                },
+            };
+
+            let effect = exec::execute(self, decoded.opcode, &decoded.params)?;
+            exec::advance(self, decoded.len, &effect)?;
+
+            if let Some(state) = exec::run_state(effect) {
+                return Ok(Some(state));
+            }
+        }
+    }
+
+    fn decoded_at(&mut self, pc: usize) -> Option<Decoded> {
+        if let Some(Some(decoded)) = self.cache.entries.get(pc) {
+            return Some(*decoded);
+        }
+
+        // The cache decodes the builtin opcodes itself, which is only right
+        // while the registry hasn't replaced any of them.
+        if pc >= CACHE_LIMIT || pc >= self.mem.len() || !self.registry.is_stock() {
+            return None;
+        }
+
+        let opcode = self.current_opcode().ok()?;
+        let len = opcode.modes().len();
+        let mut params = [0; 3];
+        for (i, param) in params.iter_mut().enumerate().take(len) {
+            *param = self.mem.get(pc + 1 + i);
+        }
+
+        let decoded = Decoded {
+            opcode,
+            params,
+            len: len + 1,
+        };
+
+        if self.cache.entries.len() <= pc {
+            let len = self.mem.len().clamp(pc + 1, CACHE_LIMIT);
+            self.cache.entries.resize(len, None);
+        }
+        self.cache.entries[pc] = Some(decoded);
+
+        Some(decoded)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::asm::assemble;
+    use crate::{Amp, RunState};
+
+    fn both(program: Vec<i64>, inputs: Vec<i64>) -> (Vec<RunState>, Vec<RunState>) {
+        let run = |fast: bool| {
+            let mut amp = Amp::new(program.clone());
+            amp.extend_input(inputs.clone());
+
+            let mut states = Vec::new();
+            loop {
+                let state = if fast {
+                    amp.resume_fast()
+                } else {
+                    amp.resume()
+                };
+
+                match state {
+                    Ok(RunState::Output(value)) => states.push(RunState::Output(value)),
+                    Ok(state) => {
+                        states.push(state);
+                        break;
+                    }
+                    Err(error) => panic!("{}", error),
+                }
+            }
+
+            states
+        };
+
+        (run(false), run(true))
+    }
+
+    #[test]
+    fn sees_self_modifying_writes() {
+        // Rewrites the immediate operand of its own `out` each time round.
+        let program = assemble(
+            "
+            loop:   out #0
+                    add [1], #1 -> [1]
+                    lt [1], #4 -> [flag]
+                    jt [flag], #loop
+                    hlt
+            flag:   .data 0
+            ",
+        )
+        .unwrap();
+        let program = program
+            .into_iter()
+            .enumerate()
+            .map(|(i, word)| if i == 0 { 104 } else { word })
+            .collect::<Vec<_>>();
+
+        let (slow, fast) = both(program, vec![]);
+
+        assert_eq!(fast, slow);
+        assert_eq!(
+            fast,
+            vec![
+                RunState::Output(0),
+                RunState::Output(1),
+                RunState::Output(2),
+                RunState::Output(3),
+                RunState::Halted
+            ]
+        );
+    }
+
+    #[test]
+    fn sees_rewritten_opcodes() {
+        // The first pass turns the `mul` at `op` into an `add`.
+        let program = assemble(
+            "
+                    in -> [x]
+            op:     mul [x], #3 -> [x]
+                    out [x]
+                    lt [x], #20 -> [flag]
+                    jf [flag], #done
+                    add #0, #1001 -> [op]
+                    jt #1, #op
+            done:   hlt
+            x:      .data 0
+            flag:   .data 0
+            ",
+        )
+        .unwrap();
+
+        let (slow, fast) = both(program, vec![5]);
+
+        assert_eq!(fast, slow);
+        assert_eq!(
+            fast,
+            vec![
+                RunState::Output(15),
+                RunState::Output(18),
+                RunState::Output(21),
+                RunState::Halted
+            ]
+        );
+    }
+
+    #[test]
+    fn blocks_on_input_like_resume() {
+        let program = vec![3, 9, 4, 9, 3, 10, 4, 10, 99, 0, 0];
+
+        let (slow, fast) = both(program, vec![7]);
+
+        assert_eq!(fast, slow);
+        assert_eq!(fast, vec![RunState::Output(7), RunState::NeedsInput]);
+    }
+}
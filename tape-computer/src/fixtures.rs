@@ -0,0 +1,14 @@
+// Small programs shared by the unit tests.
+
+// Reads a number and echoes it back until it reads a zero.
+pub(crate) const ECHO: [i64; 12] = [3, 11, 1006, 11, 10, 4, 11, 1105, 1, 0, 99, 0];
+
+// Squares its input and prints the result.
+pub(crate) const SQUARE: [i64; 10] = [3, 9, 2, 9, 9, 9, 4, 9, 99, 0];
+
+// Day 7's second feedback-loop example: the best phases, 9,8,7,6,5, give a
+// signal of 139629729.
+pub(crate) const FEEDBACK: [i64; 29] = [
+    3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1, 28, 1005,
+    28, 6, 99, 0, 0, 5,
+];
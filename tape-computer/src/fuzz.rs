@@ -0,0 +1,515 @@
+use crate::symbolic::{Symbolic, SymbolicError};
+use crate::word::{Word, WordAmp};
+use crate::{Amp, IntcodeError, Overflow, RunState};
+use num_bigint::BigInt;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum End {
+    Halted,
+    NeedsInput,
+    Error(IntcodeError),
+    OutOfBudget,
+    Panicked(String),
+    // A failure of the engine's own that no Intcode error describes.
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Outcome {
+    pub outputs: Vec<i64>,
+    pub end: End,
+}
+
+// An interpreter under test. `run` executes a program on the given inputs
+// for at most `budget` instructions and stops when it halts, faults or
+// runs out of input.
+#[derive(Copy, Clone)]
+pub struct Engine {
+    pub name: &'static str,
+    pub run: fn(&[i64], &[i64], usize) -> Outcome,
+    // Computes past i64, so it is only compared on runs where the reference
+    // engine didn't overflow.
+    pub wide: bool,
+}
+
+impl Engine {
+    // `Amp::step` first, as the reference the others are compared with.
+    pub fn builtin() -> Vec<Engine> {
+        vec![
+            Engine {
+                name: "step",
+                run: stepped,
+                wide: false,
+            },
+            Engine {
+                name: "fast",
+                run: fast,
+                wide: false,
+            },
+            Engine {
+                name: "bigint",
+                run: word::<BigInt>,
+                wide: true,
+            },
+            Engine {
+                name: "word-i64",
+                run: word::<i64>,
+                wide: false,
+            },
+            Engine {
+                name: "symbolic",
+                run: symbolic,
+                wide: false,
+            },
+        ]
+    }
+}
+
+fn stepped(program: &[i64], inputs: &[i64], budget: usize) -> Outcome {
+    let mut amp = Amp::new(program.to_vec());
+    amp.extend_input(inputs.iter().copied());
+
+    let mut outputs = Vec::new();
+    for _ in 0..budget {
+        let end = match amp.step() {
+            Ok(None) => continue,
+            Ok(Some(RunState::Output(value))) => {
+                outputs.push(value);
+                continue;
+            }
+            Ok(Some(RunState::NeedsInput)) => End::NeedsInput,
+            Ok(Some(RunState::Halted)) => End::Halted,
+            Err(error) => End::Error(error),
+        };
+
+        return Outcome { outputs, end };
+    }
+
+    Outcome {
+        outputs,
+        end: End::OutOfBudget,
+    }
+}
+
+fn fast(program: &[i64], inputs: &[i64], mut budget: usize) -> Outcome {
+    let mut amp = Amp::new(program.to_vec());
+    amp.extend_input(inputs.iter().copied());
+
+    let mut outputs = Vec::new();
+    let end = loop {
+        match amp.resume_fast_bounded(&mut budget) {
+            Ok(Some(RunState::Output(value))) => outputs.push(value),
+            Ok(Some(RunState::NeedsInput)) => break End::NeedsInput,
+            Ok(Some(RunState::Halted)) => break End::Halted,
+            Ok(None) => break End::OutOfBudget,
+            Err(error) => break End::Error(error),
+        }
+    };
+
+    Outcome { outputs, end }
+}
+
+// Overflow is checked, as it is for `Amp`, so narrow engines agree with it.
+fn word<W: Word + From<i64>>(program: &[i64], inputs: &[i64], budget: usize) -> Outcome {
+    let mut amp = WordAmp::<W>::new(program.to_vec());
+    amp.set_overflow(Overflow::Checked);
+    for &input in inputs {
+        amp.push_input(input);
+    }
+
+    let mut outputs = Vec::new();
+    for _ in 0..budget {
+        let end = match amp.step() {
+            Ok(None) => continue,
+            Ok(Some(RunState::Output(value))) => match value.to_i64() {
+                Some(value) => {
+                    outputs.push(value);
+                    continue;
+                }
+                None => End::Error(IntcodeError::Overflow { pc: amp.pc() }),
+            },
+            Ok(Some(RunState::NeedsInput)) => End::NeedsInput,
+            Ok(Some(RunState::Halted)) => End::Halted,
+            Err(error) => End::Error(error),
+        };
+
+        return Outcome { outputs, end };
+    }
+
+    Outcome {
+        outputs,
+        end: End::OutOfBudget,
+    }
+}
+
+// Fed only concrete inputs, so every word stays a constant.
+fn symbolic(program: &[i64], inputs: &[i64], budget: usize) -> Outcome {
+    let mut machine = Symbolic::new(program);
+    for &input in inputs {
+        machine.push_input(input);
+    }
+
+    let mut outputs = Vec::new();
+    for _ in 0..budget {
+        let end = match machine.step() {
+            Ok(None) => continue,
+            Ok(Some(RunState::Output(value))) => match value.constant() {
+                Some(value) => {
+                    outputs.push(value);
+                    continue;
+                }
+                None => End::Failed(format!("output {:?} isn't constant", value)),
+            },
+            Ok(Some(RunState::NeedsInput)) => End::NeedsInput,
+            Ok(Some(RunState::Halted)) => End::Halted,
+            Err(SymbolicError::Intcode(error)) => End::Error(error),
+            Err(error) => End::Failed(error.to_string()),
+        };
+
+        return Outcome { outputs, end };
+    }
+
+    Outcome {
+        outputs,
+        end: End::OutOfBudget,
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Case {
+    pub program: Vec<i64>,
+    pub inputs: Vec<i64>,
+}
+
+impl Case {
+    // Shrinking only ever moves to a smaller case, so it always finishes.
+    fn size(&self) -> (usize, u64) {
+        let words = self.program.iter().chain(&self.inputs);
+
+        (
+            self.program.len() + self.inputs.len(),
+            words.map(|word| word.unsigned_abs()).sum(),
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Divergence {
+    pub case: Case,
+    pub outcomes: Vec<(&'static str, Outcome)>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let join = |words: &[i64]| {
+            let words = words.iter().map(i64::to_string).collect::<Vec<_>>();
+            words.join(",")
+        };
+
+        writeln!(f, "program: {}", join(&self.case.program))?;
+        writeln!(f, "inputs: {}", join(&self.case.inputs))?;
+        for (name, outcome) in &self.outcomes {
+            writeln!(
+                f,
+                "{}: outputs [{}], {:?}",
+                name,
+                join(&outcome.outputs),
+                outcome.end
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+// xorshift64*, so a seed always generates the same cases.
+#[derive(Debug, Clone)]
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Rng(seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn between(&mut self, low: i64, high: i64) -> i64 {
+        low + (self.next() % (high - low + 1) as u64) as i64
+    }
+}
+
+// Builtin opcodes with how many parameters each reads and writes.
+const SHAPES: [(i64, usize, usize); 9] = [
+    (1, 2, 1),
+    (2, 2, 1),
+    (3, 0, 1),
+    (4, 1, 0),
+    (5, 2, 0),
+    (6, 2, 0),
+    (7, 2, 1),
+    (8, 2, 1),
+    (9, 1, 0),
+];
+
+pub struct Fuzzer {
+    rng: Rng,
+    engines: Vec<Engine>,
+    pub budget: usize,
+}
+
+impl Fuzzer {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng::new(seed),
+            engines: Engine::builtin(),
+            budget: 10_000,
+        }
+    }
+
+    pub fn add_engine(&mut self, engine: Engine) {
+        self.engines.push(engine);
+    }
+
+    // A run of well-formed instructions ending in a halt, followed by some
+    // data. Position parameters point anywhere in the program, so writes
+    // into code happen regularly; immediate jumps land on instructions.
+    pub fn generate(&mut self) -> Case {
+        let mut program = Vec::new();
+        let mut starts = Vec::new();
+        let mut positions = Vec::new();
+        let mut jumps = Vec::new();
+
+        for _ in 0..=self.rng.below(12) {
+            let (opcode, reads, writes) = SHAPES[self.rng.below(SHAPES.len())];
+            let at = program.len();
+            starts.push(at);
+            program.push(opcode);
+
+            for i in 0..reads + writes {
+                let mode = if i < reads {
+                    self.rng.below(3)
+                } else {
+                    [0, 2][self.rng.below(2)]
+                };
+                program[at] += mode as i64 * 10_i64.pow(i as u32 + 2);
+
+                match mode {
+                    0 => positions.push(program.len()),
+                    1 if (opcode == 5 || opcode == 6) && i == 1 => jumps.push(program.len()),
+                    _ => {}
+                }
+                program.push(self.rng.between(-3, 12));
+            }
+        }
+
+        starts.push(program.len());
+        program.push(99);
+        for _ in 0..self.rng.below(6) {
+            program.push(self.rng.between(-20, 20));
+        }
+
+        for at in positions {
+            program[at] = self.rng.below(program.len() + 2) as i64;
+        }
+        for at in jumps {
+            program[at] = starts[self.rng.below(starts.len())] as i64;
+        }
+
+        let inputs = (0..self.rng.below(6))
+            .map(|_| self.rng.between(-20, 20))
+            .collect();
+
+        Case { program, inputs }
+    }
+
+    // Runs every engine on `case`. Cases that exhaust the budget on the
+    // reference engine are skipped, since they can't be compared, and wide
+    // engines sit out runs that overflow rather than grow huge numbers.
+    pub fn compare(&self, case: &Case) -> Option<Divergence> {
+        let reference = self.execute(&self.engines[0], case);
+        if reference.end == End::OutOfBudget {
+            return None;
+        }
+
+        let overflowed = matches!(reference.end, End::Error(IntcodeError::Overflow { .. }));
+        let mut outcomes = vec![(self.engines[0].name, reference)];
+        for engine in &self.engines[1..] {
+            if !(engine.wide && overflowed) {
+                outcomes.push((engine.name, self.execute(engine, case)));
+            }
+        }
+
+        let reference = &outcomes[0].1;
+        if outcomes.iter().all(|(_, outcome)| outcome == reference) {
+            return None;
+        }
+
+        Some(Divergence {
+            case: case.clone(),
+            outcomes,
+        })
+    }
+
+    // Greedily drops inputs and words and moves words towards zero for as
+    // long as the engines still disagree.
+    pub fn shrink(&self, mut divergence: Divergence) -> Divergence {
+        loop {
+            let found = candidates(&divergence.case)
+                .into_iter()
+                .filter(|case| case.size() < divergence.case.size())
+                .find_map(|case| self.compare(&case));
+
+            match found {
+                Some(smaller) => divergence = smaller,
+                None => return divergence,
+            }
+        }
+    }
+
+    // Generates and compares up to `cases` programs, returning the first
+    // divergence after shrinking it.
+    pub fn run(&mut self, cases: usize) -> Option<Divergence> {
+        for _ in 0..cases {
+            let case = self.generate();
+            if let Some(divergence) = self.compare(&case) {
+                return Some(self.shrink(divergence));
+            }
+        }
+
+        None
+    }
+
+    fn execute(&self, engine: &Engine, case: &Case) -> Outcome {
+        let run = || (engine.run)(&case.program, &case.inputs, self.budget);
+
+        panic::catch_unwind(AssertUnwindSafe(run)).unwrap_or_else(|payload| {
+            let message = match payload.downcast::<String>() {
+                Ok(message) => *message,
+                Err(payload) => payload
+                    .downcast_ref::<&str>()
+                    .map_or_else(|| "unknown panic".to_string(), |s| s.to_string()),
+            };
+
+            Outcome {
+                outputs: Vec::new(),
+                end: End::Panicked(message),
+            }
+        })
+    }
+}
+
+fn candidates(case: &Case) -> Vec<Case> {
+    let mut candidates = Vec::new();
+
+    for i in 0..case.inputs.len() {
+        let mut smaller = case.clone();
+        smaller.inputs.remove(i);
+        candidates.push(smaller);
+    }
+
+    for i in (0..case.program.len()).rev() {
+        let mut smaller = case.clone();
+        smaller.program.remove(i);
+        candidates.push(smaller);
+    }
+
+    for (i, &word) in case.program.iter().enumerate() {
+        for value in &[0, word / 2] {
+            let mut smaller = case.clone();
+            smaller.program[i] = *value;
+            candidates.push(smaller);
+        }
+    }
+
+    candidates
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fuzz::{candidates, Case, End, Engine, Fuzzer, Outcome};
+
+    fn bad_output(program: &[i64], inputs: &[i64], budget: usize) -> Outcome {
+        let mut outcome = (Engine::builtin()[0].run)(program, inputs, budget);
+        for value in &mut outcome.outputs {
+            *value = value.abs();
+        }
+
+        outcome
+    }
+
+    fn bad_decode(program: &[i64], _: &[i64], _: usize) -> Outcome {
+        let _ = program[program.len()];
+
+        Outcome {
+            outputs: vec![],
+            end: End::Halted,
+        }
+    }
+
+    #[test]
+    fn builtin_engines_agree() {
+        let mut fuzzer = Fuzzer::new(2019);
+
+        let actual = fuzzer.run(2_000).map(|divergence| divergence.to_string());
+
+        assert_eq!(actual, None);
+    }
+
+    #[test]
+    fn shrinks_divergence_until_no_step_helps() {
+        let mut fuzzer = Fuzzer::new(7);
+        fuzzer.add_engine(Engine {
+            name: "abs",
+            run: bad_output,
+            wide: false,
+        });
+
+        let divergence = fuzzer.run(2_000).expect("no divergence found");
+        let case = &divergence.case;
+
+        // No single smaller step away from the result still diverges.
+        assert!(candidates(case)
+            .iter()
+            .filter(|smaller| smaller.size() < case.size())
+            .all(|smaller| fuzzer.compare(smaller).is_none()));
+        assert!(fuzzer.compare(case).is_some());
+    }
+
+    #[test]
+    fn catches_engine_panics() {
+        let mut fuzzer = Fuzzer::new(1);
+        fuzzer.add_engine(Engine {
+            name: "panics",
+            run: bad_decode,
+            wide: false,
+        });
+        let case = Case {
+            program: vec![99],
+            inputs: vec![],
+        };
+
+        let divergence = fuzzer.compare(&case).expect("panic not reported");
+
+        let (_, panicked) = divergence
+            .outcomes
+            .iter()
+            .find(|(name, _)| *name == "panics")
+            .unwrap();
+        assert!(matches!(
+            panicked.end,
+            End::Panicked(ref message) if message.contains("out of bounds")
+        ));
+    }
+}
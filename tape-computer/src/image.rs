@@ -0,0 +1,439 @@
+use crate::disasm::{disassemble, Item, Line};
+use crate::load::{self, LoadError};
+use crate::Amp;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{self, Write};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub const MAGIC: [u8; 4] = *b"ICIM";
+pub const VERSION: u8 = 1;
+
+const SYMBOLS: u8 = 1;
+const COMMENTS: u8 = 2;
+
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Image {
+    pub entry: usize,
+    pub words: Vec<i64>,
+    pub symbols: BTreeMap<String, usize>,
+    pub comments: BTreeMap<usize, String>,
+}
+
+#[derive(Debug)]
+pub enum ImageError {
+    Io(io::Error),
+    BadMagic,
+    UnsupportedVersion(u8),
+    Truncated,
+    Overlong,
+    InvalidText,
+    UnknownSection(u8),
+    DuplicateSection(u8),
+    // The entry point isn't the address of any word.
+    EntryOutOfRange(usize),
+    // The image has an entry point, symbols or comments, which plain text
+    // can't hold.
+    NotPlainText,
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ImageError::Io(error) => write!(f, "{}", error),
+            ImageError::BadMagic => write!(f, "not an intcode image"),
+            ImageError::UnsupportedVersion(version) => {
+                write!(f, "unsupported image version {}", version)
+            }
+            ImageError::Truncated => write!(f, "image is truncated"),
+            ImageError::Overlong => write!(f, "varint does not fit in 64 bits"),
+            ImageError::InvalidText => write!(f, "symbol or comment is not valid utf-8"),
+            ImageError::UnknownSection(tag) => write!(f, "unknown section {}", tag),
+            ImageError::DuplicateSection(tag) => write!(f, "section {} appears twice", tag),
+            ImageError::EntryOutOfRange(entry) => {
+                write!(f, "entry point {} is past the last word", entry)
+            }
+            ImageError::NotPlainText => write!(
+                f,
+                "image has an entry point or annotations that plain text can't hold"
+            ),
+        }
+    }
+}
+
+impl Error for ImageError {}
+
+impl From<io::Error> for ImageError {
+    fn from(error: io::Error) -> Self {
+        ImageError::Io(error)
+    }
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+fn put_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn put_text(bytes: &mut Vec<u8>, text: &str) {
+    put_varint(bytes, text.len() as u64);
+    bytes.extend_from_slice(text.as_bytes());
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn byte(&mut self) -> Result<u8, ImageError> {
+        let (&byte, rest) = self.bytes.split_first().ok_or(ImageError::Truncated)?;
+        self.bytes = rest;
+
+        Ok(byte)
+    }
+
+    fn varint(&mut self) -> Result<u64, ImageError> {
+        let mut value = 0;
+
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            if shift == 63 && byte > 1 {
+                return Err(ImageError::Overlong);
+            }
+
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(ImageError::Overlong)
+    }
+
+    fn usize(&mut self) -> Result<usize, ImageError> {
+        let value = self.varint()?;
+        if value > usize::MAX as u64 {
+            return Err(ImageError::Overlong);
+        }
+
+        Ok(value as usize)
+    }
+
+    fn text(&mut self) -> Result<String, ImageError> {
+        let len = self.usize()?;
+        if len > self.bytes.len() {
+            return Err(ImageError::Truncated);
+        }
+
+        let (text, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+
+        String::from_utf8(text.to_vec()).map_err(|_| ImageError::InvalidText)
+    }
+}
+
+impl Image {
+    pub fn new(words: Vec<i64>) -> Self {
+        Self {
+            words,
+            ..Self::default()
+        }
+    }
+
+    // Header, then zigzag varint words, then a tagged section for each kind
+    // of annotation that is present.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        put_varint(&mut bytes, self.words.len() as u64);
+        put_varint(&mut bytes, self.entry as u64);
+
+        for &word in &self.words {
+            put_varint(&mut bytes, zigzag(word));
+        }
+
+        if !self.symbols.is_empty() {
+            bytes.push(SYMBOLS);
+            put_varint(&mut bytes, self.symbols.len() as u64);
+            for (name, &addr) in &self.symbols {
+                put_text(&mut bytes, name);
+                put_varint(&mut bytes, addr as u64);
+            }
+        }
+
+        if !self.comments.is_empty() {
+            bytes.push(COMMENTS);
+            put_varint(&mut bytes, self.comments.len() as u64);
+            for (&addr, comment) in &self.comments {
+                put_varint(&mut bytes, addr as u64);
+                put_text(&mut bytes, comment);
+            }
+        }
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ImageError> {
+        if !bytes.starts_with(&MAGIC) {
+            return Err(ImageError::BadMagic);
+        }
+
+        let mut reader = Reader {
+            bytes: &bytes[MAGIC.len()..],
+        };
+        let version = reader.byte()?;
+        if version != VERSION {
+            return Err(ImageError::UnsupportedVersion(version));
+        }
+
+        let count = reader.usize()?;
+        let mut image = Image {
+            entry: reader.usize()?,
+            // Every word takes at least a byte, which bounds the allocation.
+            words: Vec::with_capacity(count.min(reader.bytes.len())),
+            ..Image::default()
+        };
+
+        for _ in 0..count {
+            image.words.push(unzigzag(reader.varint()?));
+        }
+
+        // An empty image still starts at 0.
+        if image.entry != 0 && image.entry >= image.words.len() {
+            return Err(ImageError::EntryOutOfRange(image.entry));
+        }
+
+        let mut seen = Vec::new();
+        while !reader.bytes.is_empty() {
+            let tag = reader.byte()?;
+            if tag != SYMBOLS && tag != COMMENTS {
+                return Err(ImageError::UnknownSection(tag));
+            }
+            if seen.contains(&tag) {
+                return Err(ImageError::DuplicateSection(tag));
+            }
+            seen.push(tag);
+            let entries = reader.usize()?;
+
+            for _ in 0..entries {
+                if tag == SYMBOLS {
+                    let name = reader.text()?;
+                    image.symbols.insert(name, reader.usize()?);
+                } else {
+                    let addr = reader.usize()?;
+                    image.comments.insert(addr, reader.text()?);
+                }
+            }
+        }
+
+        Ok(image)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), ImageError> {
+        fs::write(path, self.to_bytes())?;
+
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ImageError> {
+        Image::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, LoadError> {
+        load::parse(text).map(Image::new)
+    }
+
+    // Only the words survive as text, so an image with anything else is
+    // refused rather than quietly losing it; `listing` keeps everything.
+    pub fn to_text(&self) -> Result<String, ImageError> {
+        if self.entry != 0 || !self.symbols.is_empty() || !self.comments.is_empty() {
+            return Err(ImageError::NotPlainText);
+        }
+
+        let words = self.words.iter().map(i64::to_string).collect::<Vec<_>>();
+
+        Ok(words.join(","))
+    }
+
+    // A disassembly with the image's symbols as labels and its comments
+    // above the words they annotate; `asm::assemble_image` reads it back.
+    pub fn listing(&self) -> String {
+        let mut labels = BTreeMap::new();
+        for (name, &addr) in &self.symbols {
+            labels
+                .entry(addr)
+                .or_insert_with(Vec::new)
+                .push(name.as_str());
+        }
+        let annotated = |addr| labels.contains_key(&addr) || self.comments.contains_key(&addr);
+
+        let mut lines = Vec::new();
+        for line in disassemble(&self.words, self.entry).lines {
+            let width = match &line.item {
+                Item::Instruction { params, .. } => params.len() + 1,
+                Item::Data(_) => 1,
+            };
+
+            // An annotation inside an instruction can only be kept by
+            // listing its words as data.
+            if (line.addr + 1..line.addr + width).any(annotated) {
+                lines.extend((line.addr..line.addr + width).map(|addr| Line {
+                    addr,
+                    item: Item::Data(self.words[addr]),
+                }));
+            } else {
+                lines.push(line);
+            }
+        }
+
+        let mut text = String::new();
+        if self.entry != 0 {
+            writeln!(text, ".entry {}", self.entry).unwrap();
+        }
+
+        let mut annotate = |text: &mut String, addr| {
+            for name in labels.remove(&addr).unwrap_or_default() {
+                writeln!(text, "{}:", name).unwrap();
+            }
+            if let Some(comment) = self.comments.get(&addr) {
+                for comment in comment.split('\n') {
+                    writeln!(text, "; {}", comment).unwrap();
+                }
+            }
+        };
+
+        for line in lines {
+            annotate(&mut text, line.addr);
+            writeln!(text, "{}", line).unwrap();
+        }
+
+        // Labels just past the last word mark the end of the program.
+        annotate(&mut text, self.words.len());
+
+        text
+    }
+}
+
+impl From<&Image> for Amp {
+    fn from(image: &Image) -> Self {
+        let mut amp = Amp::new(image.words.clone());
+        amp.pc = image.entry;
+
+        amp
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::asm::assemble_image;
+    use crate::image::{unzigzag, zigzag, Image, ImageError};
+    use crate::{Amp, RunState};
+
+    fn annotated() -> Image {
+        assemble_image(
+            "
+                    .entry start
+            ; counts down from the input
+            start:  in -> [n]
+            loop:   out [n]         ; print it
+                    add [n], #-1 -> [n]
+                    jt [n], #loop
+                    hlt
+            n:      .data 0
+            ",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn zigzag_round_trips_extremes() {
+        for &value in &[0, -1, 1, i64::MIN, i64::MAX, -1_000_000_007] {
+            assert_eq!(unzigzag(zigzag(value)), value);
+        }
+    }
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let mut image = annotated();
+        image.words.push(i64::MIN);
+
+        let bytes = image.to_bytes();
+
+        assert_eq!(&bytes[..5], b"ICIM\x01");
+        assert_eq!(Image::from_bytes(&bytes).unwrap(), image);
+    }
+
+    #[test]
+    fn round_trips_through_listing() {
+        let mut image = annotated();
+        image.symbols.insert("operand".to_string(), 3);
+        image.comments.insert(6, String::new());
+
+        let actual = assemble_image(&image.listing()).unwrap();
+
+        assert_eq!(actual, image);
+    }
+
+    #[test]
+    fn rejects_damaged_images() {
+        let bytes = annotated().to_bytes();
+
+        assert!(matches!(
+            Image::from_bytes(b"ICIX\x01"),
+            Err(ImageError::BadMagic)
+        ));
+        assert!(matches!(
+            Image::from_bytes(b"ICIM\x02"),
+            Err(ImageError::UnsupportedVersion(2))
+        ));
+        assert!(matches!(
+            Image::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(ImageError::Truncated)
+        ));
+        // An empty section with an unknown tag is still an unknown section.
+        assert!(matches!(
+            Image::from_bytes(b"ICIM\x01\x00\x00\x07\x00"),
+            Err(ImageError::UnknownSection(7))
+        ));
+        assert!(matches!(
+            Image::from_bytes(b"ICIM\x01\x01\x01\x00"),
+            Err(ImageError::EntryOutOfRange(1))
+        ));
+        assert!(matches!(
+            Image::from_bytes(b"ICIM\x01\x00\x00\x02\x00\x02\x00"),
+            Err(ImageError::DuplicateSection(2))
+        ));
+    }
+
+    #[test]
+    fn refuses_text_that_would_lose_annotations() {
+        assert_eq!(Image::new(vec![1, -2]).to_text().unwrap(), "1,-2");
+        assert!(matches!(
+            annotated().to_text(),
+            Err(ImageError::NotPlainText)
+        ));
+    }
+
+    #[test]
+    fn amp_starts_at_entry_point() {
+        let image = Image {
+            entry: 2,
+            ..Image::new(vec![104, 1, 104, 2, 99])
+        };
+        let mut amp = Amp::from(&image);
+
+        assert_eq!(amp.resume(), Ok(RunState::Output(2)));
+    }
+}
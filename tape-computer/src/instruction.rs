@@ -0,0 +1,502 @@
+use crate::exec;
+use crate::{Amp, IntcodeError, Memory, Mode, Opcode, Overflow};
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+
+// Opcodes are the low two digits of an instruction word.
+const OPCODES: usize = 100;
+
+// What the machine does after an instruction has executed. `Next` and
+// `Output` move the pc past the instruction's parameters; `NeedsInput` and
+// `Halt` leave it where it is so the instruction runs again on resume.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Effect<W = i64> {
+    Next,
+    Jump(W),
+    Output(W),
+    NeedsInput,
+    Halt,
+}
+
+pub trait Instruction: Send + Sync {
+    fn opcode(&self) -> i64;
+
+    fn mnemonic(&self) -> &str;
+
+    // The number of parameter words following the opcode. Each one gets a
+    // mode digit, so at most eight parameters can be addressed.
+    fn arity(&self) -> usize;
+
+    // Whether the last parameter is a write target rather than a read. Only
+    // traces use this; `execute` still does the writing.
+    fn writes(&self) -> bool {
+        false
+    }
+
+    fn execute(&self, cpu: &mut Cpu<'_>) -> Result<Effect, IntcodeError>;
+}
+
+// The machine as seen by an executing instruction. Parameters are numbered
+// from zero and resolved using the instruction word's mode digits.
+pub struct Cpu<'a> {
+    amp: &'a mut Amp,
+    word: i64,
+}
+
+impl<'a> Cpu<'a> {
+    pub fn pc(&self) -> usize {
+        self.amp.pc
+    }
+
+    pub fn mode(&self, param: usize) -> Result<Mode, IntcodeError> {
+        mode(self.word, param, self.amp.pc)
+    }
+
+    // The raw parameter word, before its mode is applied.
+    pub fn param(&self, param: usize) -> i64 {
+        self.amp.mem.get(self.amp.pc + 1 + param)
+    }
+
+    pub fn address(&self, param: usize) -> Result<usize, IntcodeError> {
+        exec::address(self.amp, param + 1, self.mode(param)?)
+    }
+
+    pub fn read(&self, param: usize) -> Result<i64, IntcodeError> {
+        exec::read(self.amp, param + 1, self.mode(param)?)
+    }
+
+    pub fn write(&mut self, param: usize, value: i64) -> Result<(), IntcodeError> {
+        let mode = self.mode(param)?;
+        exec::write(self.amp, param + 1, mode, value)
+    }
+
+    pub fn input(&mut self) -> Option<i64> {
+        self.amp.input_buffer.pop_front()
+    }
+
+    pub fn relative_base(&self) -> i64 {
+        self.amp.relative
+    }
+
+    pub fn set_relative_base(&mut self, relative: i64) {
+        self.amp.relative = relative;
+    }
+
+    pub fn overflow(&self) -> Overflow {
+        self.amp.overflow
+    }
+
+    pub fn add(&self, a: i64, b: i64) -> Result<i64, IntcodeError> {
+        self.amp.overflow.add(a, b, self.amp.pc)
+    }
+
+    pub fn mul(&self, a: i64, b: i64) -> Result<i64, IntcodeError> {
+        self.amp.overflow.mul(a, b, self.amp.pc)
+    }
+
+    pub fn memory(&self) -> &Memory {
+        &self.amp.mem
+    }
+
+    pub fn memory_mut(&mut self) -> &mut Memory {
+        self.amp.memory_mut()
+    }
+}
+
+fn mode(word: i64, param: usize, pc: usize) -> Result<Mode, IntcodeError> {
+    Mode::decode((word / 10_i64.pow(param as u32 + 2)) % 10, pc)
+}
+
+#[derive(Clone)]
+pub struct Registry {
+    table: Vec<Option<Arc<dyn Instruction>>>,
+    // Which opcodes still map to their builtin instruction.
+    builtin: Vec<bool>,
+    // Set while every builtin opcode does, which lets `Amp::resume_fast` use
+    // its own decoder.
+    stock: bool,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self {
+            table: vec![None; OPCODES],
+            builtin: vec![false; OPCODES],
+            stock: false,
+        }
+    }
+
+    // The ten opcodes of the finished Intcode computer.
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        for instruction in builtins() {
+            registry.register(instruction);
+        }
+        for &opcode in &BUILTIN_OPCODES {
+            registry.builtin[opcode as usize] = true;
+        }
+        registry.stock = true;
+
+        registry
+    }
+
+    pub(crate) fn shared() -> Arc<Self> {
+        static BUILTIN: OnceLock<Arc<Registry>> = OnceLock::new();
+
+        Arc::clone(BUILTIN.get_or_init(|| Arc::new(Registry::builtin())))
+    }
+
+    // Adds an instruction, returning the one it replaces.
+    pub fn register(&mut self, instruction: Arc<dyn Instruction>) -> Option<Arc<dyn Instruction>> {
+        let opcode = instruction.opcode();
+        assert!(
+            (0..OPCODES as i64).contains(&opcode),
+            "opcode {} doesn't fit in two digits",
+            opcode
+        );
+        assert!(
+            instruction.arity() <= 8,
+            "{} has more parameters than mode digits",
+            instruction.mnemonic()
+        );
+
+        self.builtin[opcode as usize] = false;
+        if BUILTIN_OPCODES.contains(&opcode) {
+            self.stock = false;
+        }
+
+        self.table[opcode as usize].replace(instruction)
+    }
+
+    pub fn unregister(&mut self, opcode: i64) -> Option<Arc<dyn Instruction>> {
+        if BUILTIN_OPCODES.contains(&opcode) {
+            self.stock = false;
+        }
+
+        let index = usize::try_from(opcode).ok()?;
+        *self.builtin.get_mut(index)? = false;
+        self.table.get_mut(index)?.take()
+    }
+
+    pub fn get(&self, opcode: i64) -> Option<&Arc<dyn Instruction>> {
+        self.table.get(usize::try_from(opcode).ok()?)?.as_ref()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<dyn Instruction>> {
+        self.table.iter().flatten()
+    }
+
+    pub(crate) fn is_stock(&self) -> bool {
+        self.stock
+    }
+
+    pub(crate) fn is_builtin(&self, opcode: i64) -> bool {
+        usize::try_from(opcode)
+            .ok()
+            .and_then(|opcode| self.builtin.get(opcode).copied())
+            .unwrap_or(false)
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl fmt::Debug for Registry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_map()
+            .entries(self.iter().map(|i| (i.opcode(), i.mnemonic().to_string())))
+            .finish()
+    }
+}
+
+impl Amp {
+    pub fn with_registry(mem: Vec<i64>, registry: Arc<Registry>) -> Self {
+        let mut amp = Amp::new(mem);
+        amp.registry = registry;
+
+        amp
+    }
+
+    pub fn registry(&self) -> &Arc<Registry> {
+        &self.registry
+    }
+
+    pub fn set_registry(&mut self, registry: Arc<Registry>) {
+        self.registry = registry;
+    }
+
+    // The registered instruction at the pc and the modes of its parameters.
+    pub fn current_instruction(&self) -> Result<(Arc<dyn Instruction>, Vec<Mode>), IntcodeError> {
+        let word = self.mem.get(self.pc);
+        let instruction = self
+            .registry
+            .get(word % 100)
+            .ok_or(IntcodeError::UnknownOpcode {
+                pc: self.pc,
+                opcode: word,
+            })?;
+        let modes = (0..instruction.arity())
+            .map(|param| mode(word, param, self.pc))
+            .collect::<Result<_, _>>()?;
+
+        Ok((Arc::clone(instruction), modes))
+    }
+
+    // Executes the instruction at the pc through the registry; see `step`.
+    pub(crate) fn dispatch(&mut self, word: i64) -> Result<Effect, IntcodeError> {
+        let opcode = word % 100;
+        let registry = Arc::clone(&self.registry);
+        let instruction = registry.get(opcode).ok_or(IntcodeError::UnknownOpcode {
+            pc: self.pc,
+            opcode: word,
+        })?;
+
+        // Every mode digit is checked up front, as the builtin decoder does.
+        for param in 0..instruction.arity() {
+            mode(word, param, self.pc)?;
+        }
+
+        let effect = instruction.execute(&mut Cpu { amp: self, word })?;
+        exec::advance(self, instruction.arity() + 1, &effect)?;
+
+        Ok(effect)
+    }
+}
+
+const BUILTIN_OPCODES: [i64; 10] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 99];
+
+fn builtins() -> Vec<Arc<dyn Instruction>> {
+    BUILTIN_OPCODES
+        .iter()
+        .map(|&code| {
+            let opcode = Opcode::decode(code, 0).expect("builtin opcodes decode");
+            Arc::new(Builtin {
+                code,
+                mnemonic: opcode.mnemonic(),
+                arity: opcode.modes().len(),
+                writes: opcode.writes(),
+            }) as Arc<dyn Instruction>
+        })
+        .collect()
+}
+
+// One of the ten opcodes of the finished Intcode computer, run by the same
+// code as `resume_fast` and `WordAmp`.
+struct Builtin {
+    code: i64,
+    mnemonic: &'static str,
+    arity: usize,
+    writes: bool,
+}
+
+impl Instruction for Builtin {
+    fn opcode(&self) -> i64 {
+        self.code
+    }
+
+    fn mnemonic(&self) -> &str {
+        self.mnemonic
+    }
+
+    fn arity(&self) -> usize {
+        self.arity
+    }
+
+    fn writes(&self) -> bool {
+        self.writes
+    }
+
+    fn execute(&self, cpu: &mut Cpu<'_>) -> Result<Effect, IntcodeError> {
+        let opcode = Opcode::decode(cpu.word, cpu.pc())?;
+        let params = exec::fetch(cpu.amp, self.arity);
+
+        exec::execute(cpu.amp, opcode, &params)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::instruction::{Cpu, Effect, Instruction, Registry};
+    use crate::trace::TraceEvent;
+    use crate::{Amp, IntcodeError, Mode, RunState};
+    use std::sync::{Arc, Mutex};
+
+    // dbg a: records a value without producing machine output.
+    struct DebugPrint(Arc<Mutex<Vec<i64>>>);
+
+    impl Instruction for DebugPrint {
+        fn opcode(&self) -> i64 {
+            42
+        }
+
+        fn mnemonic(&self) -> &str {
+            "dbg"
+        }
+
+        fn arity(&self) -> usize {
+            1
+        }
+
+        fn execute(&self, cpu: &mut Cpu<'_>) -> Result<Effect, IntcodeError> {
+            self.0.lock().unwrap().push(cpu.read(0)?);
+
+            Ok(Effect::Next)
+        }
+    }
+
+    // copy src, dst, len: copies `len` words between the addresses.
+    struct MemCopy;
+
+    impl Instruction for MemCopy {
+        fn opcode(&self) -> i64 {
+            20
+        }
+
+        fn mnemonic(&self) -> &str {
+            "copy"
+        }
+
+        fn arity(&self) -> usize {
+            3
+        }
+
+        fn execute(&self, cpu: &mut Cpu<'_>) -> Result<Effect, IntcodeError> {
+            let (src, dst) = (cpu.address(0)?, cpu.address(1)?);
+            let len = cpu.read(2)?.max(0) as usize;
+
+            let words = (src..src + len)
+                .map(|addr| cpu.memory().get(addr))
+                .collect::<Vec<_>>();
+            for (i, word) in words.into_iter().enumerate() {
+                cpu.memory_mut().set(dst + i, word);
+            }
+
+            Ok(Effect::Next)
+        }
+    }
+
+    // sub a, b -> c: replaces add.
+    struct Subtract;
+
+    impl Instruction for Subtract {
+        fn opcode(&self) -> i64 {
+            1
+        }
+
+        fn mnemonic(&self) -> &str {
+            "sub"
+        }
+
+        fn arity(&self) -> usize {
+            3
+        }
+
+        fn writes(&self) -> bool {
+            true
+        }
+
+        fn execute(&self, cpu: &mut Cpu<'_>) -> Result<Effect, IntcodeError> {
+            let difference = cpu.read(0)? - cpu.read(1)?;
+            cpu.write(2, difference)?;
+
+            Ok(Effect::Next)
+        }
+    }
+
+    #[test]
+    fn runs_builtins_through_the_registry() {
+        // Triples its input, then moves the relative base down one.
+        let program = vec![3, 11, 1002, 11, 3, 11, 109, -1, 4, 11, 99, 0];
+        let expected = Ok(RunState::Output(21));
+
+        let mut amp = Amp::with_registry(program, Arc::new(Registry::builtin()));
+        amp.push_input(7);
+        let actual = amp.resume();
+
+        assert_eq!(actual, expected);
+        assert_eq!(amp.relative_base(), -1);
+        assert_eq!(amp.resume(), Ok(RunState::Halted));
+    }
+
+    #[test]
+    fn plugs_in_new_opcodes() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut registry = Registry::builtin();
+        registry.register(Arc::new(DebugPrint(Arc::clone(&log))));
+        registry.register(Arc::new(MemCopy));
+
+        // dbg #5; copy [11], [14], #3; out [15]; hlt
+        let program = vec![142, 5, 10020, 11, 14, 3, 4, 15, 99, 0, 0, 7, 8, 9, 0, 0, 0];
+        let mut amp = Amp::with_registry(program, Arc::new(registry));
+
+        assert_eq!(amp.resume_fast(), Ok(RunState::Output(8)));
+        assert_eq!(amp.resume_fast(), Ok(RunState::Halted));
+        assert_eq!(amp.memory().to_vec()[14..], [7, 8, 9]);
+        assert_eq!(*log.lock().unwrap(), vec![5]);
+    }
+
+    #[test]
+    fn traces_registered_opcodes() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut registry = Registry::builtin();
+        registry.register(Arc::new(Subtract));
+        registry.register(Arc::new(DebugPrint(Arc::clone(&log))));
+
+        // sub #9, #4 -> [7]; dbg [7]; hlt
+        let mut amp = Amp::with_registry(vec![1101, 9, 4, 7, 42, 7, 99, 0], Arc::new(registry));
+        let mut events = Vec::new();
+
+        assert!(amp.current_opcode().is_err());
+        assert_eq!(amp.resume_traced(&mut events), Ok(RunState::Halted));
+        assert_eq!(
+            events,
+            vec![
+                TraceEvent {
+                    pc: 0,
+                    mnemonic: "sub".to_string(),
+                    modes: vec![Mode::Immediate, Mode::Immediate, Mode::Position],
+                    params: vec![9, 4, 7],
+                    reads: vec![9, 4],
+                    write: Some((7, 5)),
+                },
+                TraceEvent {
+                    pc: 4,
+                    mnemonic: "dbg".to_string(),
+                    modes: vec![Mode::Position],
+                    params: vec![7],
+                    reads: vec![5],
+                    write: None,
+                },
+            ]
+        );
+        assert_eq!(*log.lock().unwrap(), vec![5]);
+    }
+
+    #[test]
+    fn unregistered_opcodes_are_errors() {
+        let mut registry = Registry::builtin();
+        registry.unregister(2);
+
+        let mut amp =
+            Amp::with_registry(vec![1101, 2, 3, 0, 1002, 0, 2, 0, 99], Arc::new(registry));
+
+        assert_eq!(
+            amp.resume_fast(),
+            Err(IntcodeError::UnknownOpcode {
+                pc: 4,
+                opcode: 1002
+            })
+        );
+        assert_eq!(
+            Amp::new(vec![1042, 5, 99]).resume(),
+            Err(IntcodeError::UnknownOpcode {
+                pc: 0,
+                opcode: 1042
+            })
+        );
+    }
+}
@@ -0,0 +1,274 @@
+use crate::{Amp, IntcodeError, RunState};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::sync::mpsc::{Receiver, Sender};
+
+pub trait Input {
+    fn read(&mut self) -> Option<i64>;
+}
+
+pub trait Output {
+    fn write(&mut self, value: i64);
+}
+
+impl Input for VecDeque<i64> {
+    fn read(&mut self) -> Option<i64> {
+        self.pop_front()
+    }
+}
+
+impl<F: FnMut() -> Option<i64>> Input for F {
+    fn read(&mut self) -> Option<i64> {
+        self()
+    }
+}
+
+// Blocks until a value arrives; a hung-up sender reads as end of input.
+impl Input for Receiver<i64> {
+    fn read(&mut self) -> Option<i64> {
+        self.recv().ok()
+    }
+}
+
+impl Output for VecDeque<i64> {
+    fn write(&mut self, value: i64) {
+        self.push_back(value);
+    }
+}
+
+impl Output for Vec<i64> {
+    fn write(&mut self, value: i64) {
+        self.push(value);
+    }
+}
+
+impl<F: FnMut(i64)> Output for F {
+    fn write(&mut self, value: i64) {
+        self(value)
+    }
+}
+
+// Values sent after the receiver hangs up are dropped.
+impl Output for Sender<i64> {
+    fn write(&mut self, value: i64) {
+        self.send(value).ok();
+    }
+}
+
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct Outputs<'a> {
+    amp: &'a mut Amp,
+    stopped: Option<Result<RunState, IntcodeError>>,
+}
+
+impl<'a> Outputs<'a> {
+    pub(crate) fn new(amp: &'a mut Amp) -> Self {
+        Self { amp, stopped: None }
+    }
+
+    // Why iteration ended: NeedsInput, Halted or a fault. Any outputs not yet
+    // consumed are discarded.
+    #[must_use = "a fault is only reported here"]
+    pub fn finish(mut self) -> Result<RunState, IntcodeError> {
+        while self.next().is_some() {}
+
+        self.stopped.take().expect("iteration not finished")
+    }
+}
+
+impl Iterator for Outputs<'_> {
+    type Item = i64;
+
+    fn next(&mut self) -> Option<i64> {
+        if self.stopped.is_some() {
+            return None;
+        }
+
+        match self.amp.resume() {
+            Ok(RunState::Output(value)) => Some(value),
+            stop => {
+                self.stopped = Some(stop);
+                None
+            }
+        }
+    }
+}
+
+// Why `Chunks` couldn't finish cleanly: the machine faulted, or it stopped
+// partway through a record.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ChunkError {
+    Fault(IntcodeError),
+    Partial { state: RunState, values: Vec<i64> },
+}
+
+impl fmt::Display for ChunkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChunkError::Fault(error) => write!(f, "{}", error),
+            ChunkError::Partial { state, values } => write!(
+                f,
+                "stopped ({:?}) with a partial record {:?}",
+                state, values
+            ),
+        }
+    }
+}
+
+impl Error for ChunkError {}
+
+impl From<IntcodeError> for ChunkError {
+    fn from(error: IntcodeError) -> Self {
+        ChunkError::Fault(error)
+    }
+}
+
+#[must_use = "iterators are lazy and do nothing unless consumed"]
+pub struct Chunks<'a> {
+    outputs: Outputs<'a>,
+    size: usize,
+    // What a short final record held.
+    partial: Vec<i64>,
+}
+
+impl<'a> Chunks<'a> {
+    pub(crate) fn new(amp: &'a mut Amp, size: usize) -> Self {
+        assert!(size > 0, "chunk size must be non-zero");
+
+        Self {
+            outputs: Outputs::new(amp),
+            size,
+            partial: Vec::new(),
+        }
+    }
+
+    // As `Outputs::finish`, but stopping partway through a record is an
+    // error that carries the values it did get.
+    #[must_use = "a fault or partial record is only reported here"]
+    pub fn finish(mut self) -> Result<RunState, ChunkError> {
+        while self.next().is_some() {}
+
+        let state = self.outputs.finish()?;
+        if !self.partial.is_empty() {
+            return Err(ChunkError::Partial {
+                state,
+                values: self.partial,
+            });
+        }
+
+        Ok(state)
+    }
+}
+
+impl Iterator for Chunks<'_> {
+    type Item = Vec<i64>;
+
+    // A trailing partial record ends iteration; `finish` reports it.
+    fn next(&mut self) -> Option<Vec<i64>> {
+        let record = self.outputs.by_ref().take(self.size).collect::<Vec<_>>();
+
+        if record.len() == self.size {
+            Some(record)
+        } else {
+            self.partial.extend(record);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fixtures::ECHO;
+    use crate::{Amp, ChunkError, RunState};
+    use std::collections::VecDeque;
+    use std::sync::mpsc::channel;
+    use std::thread;
+
+    fn echo() -> Vec<i64> {
+        ECHO.to_vec()
+    }
+
+    #[test]
+    fn iterates_outputs_until_input_is_needed() {
+        let mut amp = Amp::new(echo());
+        amp.extend_input(vec![1, 2, 3]);
+
+        let mut outputs = amp.outputs();
+        let actual = outputs.by_ref().collect::<Vec<_>>();
+
+        assert_eq!(actual, vec![1, 2, 3]);
+        assert_eq!(outputs.finish(), Ok(RunState::NeedsInput));
+    }
+
+    #[test]
+    fn groups_outputs_into_records() {
+        let mut amp = Amp::new(echo());
+        amp.extend_input(vec![1, 2, 3, 4, 5, 6, 0]);
+
+        let mut chunks = amp.chunks(3);
+        let actual = chunks.by_ref().collect::<Vec<_>>();
+
+        assert_eq!(actual, vec![vec![1, 2, 3], vec![4, 5, 6]]);
+        assert_eq!(chunks.finish(), Ok(RunState::Halted));
+    }
+
+    #[test]
+    fn reports_a_trailing_partial_record() {
+        let mut amp = Amp::new(echo());
+        amp.extend_input(vec![1, 2, 3, 4, 5]);
+
+        let mut chunks = amp.chunks(3);
+        let actual = chunks.by_ref().collect::<Vec<_>>();
+
+        assert_eq!(actual, vec![vec![1, 2, 3]]);
+        assert_eq!(
+            chunks.finish(),
+            Err(ChunkError::Partial {
+                state: RunState::NeedsInput,
+                values: vec![4, 5]
+            })
+        );
+    }
+
+    #[test]
+    fn runs_with_closures() {
+        let mut amp = Amp::new(echo());
+        let mut inputs = vec![0, 9, 8];
+        let mut doubled = Vec::new();
+
+        let actual = amp.run_with(&mut || inputs.pop(), &mut |value| doubled.push(value * 2));
+
+        assert_eq!(actual, Ok(RunState::Halted));
+        assert_eq!(doubled, vec![16, 18]);
+    }
+
+    #[test]
+    fn runs_with_queues() {
+        let mut amp = Amp::new(echo());
+        let mut input = VecDeque::from(vec![4, 5]);
+        let mut output = VecDeque::new();
+
+        let actual = amp.run_with(&mut input, &mut output);
+
+        assert_eq!(actual, Ok(RunState::NeedsInput));
+        assert_eq!(output, VecDeque::from(vec![4, 5]));
+    }
+
+    #[test]
+    fn runs_over_channels() {
+        let (input_tx, mut input_rx) = channel();
+        let (mut output_tx, output_rx) = channel();
+
+        let handle = thread::spawn(move || {
+            let mut amp = Amp::new(echo());
+            amp.run_with(&mut input_rx, &mut output_tx)
+        });
+
+        input_tx.send(7).unwrap();
+        assert_eq!(output_rx.recv(), Ok(7));
+        input_tx.send(0).unwrap();
+
+        assert_eq!(handle.join().unwrap(), Ok(RunState::Halted));
+    }
+}
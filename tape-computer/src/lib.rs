@@ -1,6 +1,40 @@
+use exec::Machine;
+use instruction::Registry;
 use std::collections::VecDeque;
+use std::sync::Arc;
+use trace::{TraceEvent, Tracer};
 
-#[derive(Copy, Clone, Debug)]
+pub mod analysis;
+pub mod ascii;
+pub mod asm;
+pub mod bigint;
+pub mod debugger;
+pub mod disasm;
+mod error;
+mod exec;
+mod fast;
+#[cfg(test)]
+mod fixtures;
+pub mod fuzz;
+pub mod image;
+pub mod instruction;
+mod io;
+pub mod load;
+mod memory;
+pub mod network;
+mod overflow;
+pub mod phase;
+pub mod snapshot;
+pub mod symbolic;
+pub mod trace;
+pub mod word;
+
+pub use error::IntcodeError;
+pub use io::{ChunkError, Chunks, Input, Output, Outputs};
+pub use memory::Memory;
+pub use overflow::Overflow;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Opcode {
     Add(Mode, Mode, Mode),
     Mul(Mode, Mode, Mode),
@@ -14,30 +48,92 @@ pub enum Opcode {
     Halt,
 }
 
-impl From<i64> for Opcode {
-    fn from(input: i64) -> Self {
-        let param1_mode = Mode::from((input / 100) % 10);
-        let param2_mode = Mode::from((input / 1000) % 10);
-        let param3_mode = Mode::from((input / 10000) % 10);
-
-        match input % 100 {
-            1 => Opcode::Add(param1_mode, param2_mode, param3_mode),
-            2 => Opcode::Mul(param1_mode, param2_mode, param3_mode),
-            3 => Opcode::Save(param1_mode),
-            4 => Opcode::Output(param1_mode),
-            5 => Opcode::JIT(param1_mode, param2_mode),
-            6 => Opcode::JIF(param1_mode, param2_mode),
-            7 => Opcode::LT(param1_mode, param2_mode, param3_mode),
-            8 => Opcode::EQ(param1_mode, param2_mode, param3_mode),
-            9 => Opcode::AdjustRelative(param1_mode),
-            _ => Opcode::Halt,
+impl Opcode {
+    pub fn decode(input: i64, pc: usize) -> Result<Self, IntcodeError> {
+        let mode = |digit: u32| Mode::decode((input / 10_i64.pow(digit + 1)) % 10, pc);
+
+        let opcode = match input % 100 {
+            1 => Opcode::Add(mode(1)?, mode(2)?, mode(3)?),
+            2 => Opcode::Mul(mode(1)?, mode(2)?, mode(3)?),
+            3 => Opcode::Save(mode(1)?),
+            4 => Opcode::Output(mode(1)?),
+            5 => Opcode::JIT(mode(1)?, mode(2)?),
+            6 => Opcode::JIF(mode(1)?, mode(2)?),
+            7 => Opcode::LT(mode(1)?, mode(2)?, mode(3)?),
+            8 => Opcode::EQ(mode(1)?, mode(2)?, mode(3)?),
+            9 => Opcode::AdjustRelative(mode(1)?),
+            99 => Opcode::Halt,
+            _ => return Err(IntcodeError::UnknownOpcode { pc, opcode: input }),
+        };
+
+        Ok(opcode)
+    }
+
+    pub fn encode(&self) -> i64 {
+        let code = match self {
+            Opcode::Add(_, _, _) => 1,
+            Opcode::Mul(_, _, _) => 2,
+            Opcode::Save(_) => 3,
+            Opcode::Output(_) => 4,
+            Opcode::JIT(_, _) => 5,
+            Opcode::JIF(_, _) => 6,
+            Opcode::LT(_, _, _) => 7,
+            Opcode::EQ(_, _, _) => 8,
+            Opcode::AdjustRelative(_) => 9,
+            Opcode::Halt => 99,
+        };
+
+        self.modes()
+            .iter()
+            .enumerate()
+            .fold(code, |word, (i, &mode)| {
+                word + mode.encode() * 10_i64.pow(i as u32 + 2)
+            })
+    }
+
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Opcode::Add(_, _, _) => "add",
+            Opcode::Mul(_, _, _) => "mul",
+            Opcode::Save(_) => "in",
+            Opcode::Output(_) => "out",
+            Opcode::JIT(_, _) => "jt",
+            Opcode::JIF(_, _) => "jf",
+            Opcode::LT(_, _, _) => "lt",
+            Opcode::EQ(_, _, _) => "eq",
+            Opcode::AdjustRelative(_) => "arb",
+            Opcode::Halt => "hlt",
         }
     }
+
+    pub fn modes(&self) -> Vec<Mode> {
+        match *self {
+            Opcode::Add(m1, m2, m3)
+            | Opcode::Mul(m1, m2, m3)
+            | Opcode::LT(m1, m2, m3)
+            | Opcode::EQ(m1, m2, m3) => vec![m1, m2, m3],
+            Opcode::JIT(m1, m2) | Opcode::JIF(m1, m2) => vec![m1, m2],
+            Opcode::Save(m1) | Opcode::Output(m1) | Opcode::AdjustRelative(m1) => vec![m1],
+            Opcode::Halt => vec![],
+        }
+    }
+
+    // Whether the last parameter is a write target rather than a read.
+    pub fn writes(&self) -> bool {
+        matches!(
+            self,
+            Opcode::Add(_, _, _)
+                | Opcode::Mul(_, _, _)
+                | Opcode::Save(_)
+                | Opcode::LT(_, _, _)
+                | Opcode::EQ(_, _, _)
+        )
+    }
 }
 
-impl Into<usize> for Opcode {
-    fn into(self) -> usize {
-        match self {
+impl From<Opcode> for usize {
+    fn from(opcode: Opcode) -> Self {
+        match opcode {
             Opcode::Add(_, _, _) => 4,
             Opcode::Mul(_, _, _) => 4,
             Opcode::Save(_) => 2,
@@ -52,21 +148,37 @@ impl Into<usize> for Opcode {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Mode {
     Position,
     Immediate,
     Relative,
 }
 
-impl From<i64> for Mode {
-    fn from(input: i64) -> Self {
+impl Mode {
+    pub fn decode(input: i64, pc: usize) -> Result<Self, IntcodeError> {
         match input {
-            0 => Mode::Position,
-            2 => Mode::Relative,
-            _ => Mode::Immediate,
+            0 => Ok(Mode::Position),
+            1 => Ok(Mode::Immediate),
+            2 => Ok(Mode::Relative),
+            _ => Err(IntcodeError::InvalidMode { pc, mode: input }),
         }
     }
+
+    pub fn encode(&self) -> i64 {
+        match self {
+            Mode::Position => 0,
+            Mode::Immediate => 1,
+            Mode::Relative => 2,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RunState<W = i64> {
+    NeedsInput,
+    Output(W),
+    Halted,
 }
 
 #[derive(Debug, Clone)]
@@ -74,8 +186,11 @@ pub struct Amp {
     pc: usize,
     relative: i64,
     pub output: Option<i64>,
-    mem: Vec<i64>,
-    pub input_buffer: VecDeque<i64>,
+    mem: Memory,
+    input_buffer: VecDeque<i64>,
+    overflow: Overflow,
+    cache: fast::DecodeCache,
+    registry: Arc<Registry>,
 }
 
 impl Amp {
@@ -84,142 +199,372 @@ impl Amp {
             pc: 0,
             relative: 0,
             output: None,
-            mem,
+            mem: Memory::new(mem),
             input_buffer: VecDeque::new(),
+            overflow: Overflow::default(),
+            cache: fast::DecodeCache::default(),
+            registry: Registry::shared(),
         }
     }
 
-    pub fn run(&mut self, mut input: Option<i64>, signal: i64) -> Option<i64> {
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn relative_base(&self) -> i64 {
+        self.relative
+    }
+
+    pub fn overflow(&self) -> Overflow {
+        self.overflow
+    }
+
+    pub fn set_overflow(&mut self, overflow: Overflow) {
+        self.overflow = overflow;
+    }
+
+    pub fn memory(&self) -> &Memory {
+        &self.mem
+    }
+
+    pub fn memory_mut(&mut self) -> &mut Memory {
+        self.cache.clear();
+        &mut self.mem
+    }
+
+    pub fn pending_input(&self) -> &VecDeque<i64> {
+        &self.input_buffer
+    }
+
+    // The builtin decoding of the instruction at the pc. Opcodes the registry
+    // has added or replaced have none; `current_instruction` covers those.
+    pub fn current_opcode(&self) -> Result<Opcode, IntcodeError> {
+        let word = self.mem.get(self.pc);
+        if !self.registry.is_builtin(word % 100) {
+            return Err(IntcodeError::UnknownOpcode {
+                pc: self.pc,
+                opcode: word,
+            });
+        }
+
+        Opcode::decode(word, self.pc)
+    }
+
+    pub fn push_input(&mut self, value: i64) {
+        self.input_buffer.push_back(value);
+    }
+
+    pub fn extend_input<I: IntoIterator<Item = i64>>(&mut self, values: I) {
+        self.input_buffer.extend(values);
+    }
+
+    pub fn resume(&mut self) -> Result<RunState, IntcodeError> {
         loop {
-            let opcode = Opcode::from(self.mem[self.pc]);
-
-            match opcode {
-                Opcode::Add(m1, m2, m3) => {
-                    let (param1, param2) = (
-                        get_value(&self.mem, self.pc + 1, m1, self.relative),
-                        get_value(&self.mem, self.pc + 2, m2, self.relative),
-                    );
-
-                    set_value(
-                        &mut self.mem,
-                        self.pc + 3,
-                        m3,
-                        param1 + param2,
-                        self.relative,
-                    );
-                    self.pc += Into::<usize>::into(opcode);
-                }
-                Opcode::Mul(m1, m2, m3) => {
-                    let (param1, param2) = (
-                        get_value(&self.mem, self.pc + 1, m1, self.relative),
-                        get_value(&self.mem, self.pc + 2, m2, self.relative),
-                    );
-
-                    set_value(
-                        &mut self.mem,
-                        self.pc + 3,
-                        m3,
-                        param1 * param2,
-                        self.relative,
-                    );
-                    self.pc += Into::<usize>::into(opcode);
-                }
-                Opcode::Save(m1) => {
-                    if let Some(input) = self.input_buffer.pop_front() {
-                        set_value(&mut self.mem, &self.pc + 1, m1, input, self.relative);
-                    } else if let Some(input) = input.take() {
-                        set_value(&mut self.mem, &self.pc + 1, m1, input, self.relative);
-                    } else {
-                        set_value(&mut self.mem, &self.pc + 1, m1, signal, self.relative);
-                    }
-                    self.pc += Into::<usize>::into(opcode);
+            if let Some(state) = self.step()? {
+                return Ok(state);
+            }
+        }
+    }
+
+    pub fn run_with<I: Input, O: Output>(
+        &mut self,
+        input: &mut I,
+        output: &mut O,
+    ) -> Result<RunState, IntcodeError> {
+        loop {
+            match self.resume_fast()? {
+                RunState::NeedsInput => match input.read() {
+                    Some(value) => self.push_input(value),
+                    None => return Ok(RunState::NeedsInput),
+                },
+                RunState::Output(value) => output.write(value),
+                RunState::Halted => return Ok(RunState::Halted),
+            }
+        }
+    }
+
+    pub fn outputs(&mut self) -> Outputs<'_> {
+        Outputs::new(self)
+    }
+
+    pub fn chunks(&mut self, size: usize) -> Chunks<'_> {
+        Chunks::new(self, size)
+    }
+
+    pub fn run(
+        &mut self,
+        mut input: Option<i64>,
+        signal: i64,
+    ) -> Result<Option<i64>, IntcodeError> {
+        loop {
+            match self.resume_fast()? {
+                RunState::NeedsInput => {
+                    let value = input.take().unwrap_or(signal);
+                    self.input_buffer.push_back(value);
                 }
-                Opcode::Output(m1) => {
-                    let param1 = get_value(&self.mem, self.pc + 1, m1, self.relative);
-                    self.output = Some(param1);
-                    self.pc += Into::<usize>::into(opcode);
+                RunState::Output(value) => {
+                    self.output = Some(value);
                     break;
                 }
-                Opcode::JIT(m1, m2) => {
-                    let (param1, param2) = (
-                        get_value(&self.mem, self.pc + 1, m1, self.relative),
-                        get_value(&self.mem, self.pc + 2, m2, self.relative),
-                    );
-
-                    if param1 != 0 {
-                        self.pc = param2 as usize;
-                    } else {
-                        self.pc += Into::<usize>::into(opcode);
-                    }
-                }
-                Opcode::JIF(m1, m2) => {
-                    let (param1, param2) = (
-                        get_value(&self.mem, self.pc + 1, m1, self.relative),
-                        get_value(&self.mem, self.pc + 2, m2, self.relative),
-                    );
-
-                    if param1 == 0 {
-                        self.pc = param2 as usize;
-                    } else {
-                        self.pc += Into::<usize>::into(opcode);
-                    }
-                }
-                Opcode::LT(m1, m2, m3) => {
-                    let (param1, param2) = (
-                        get_value(&self.mem, self.pc + 1, m1, self.relative),
-                        get_value(&self.mem, self.pc + 2, m2, self.relative),
-                    );
-
-                    if param1 < param2 {
-                        set_value(&mut self.mem, self.pc + 3, m3, 1, self.relative);
-                    } else {
-                        set_value(&mut self.mem, self.pc + 3, m3, 0, self.relative);
-                    }
-
-                    self.pc += Into::<usize>::into(opcode);
-                }
-                Opcode::EQ(m1, m2, m3) => {
-                    let (param1, param2) = (
-                        get_value(&self.mem, self.pc + 1, m1, self.relative),
-                        get_value(&self.mem, self.pc + 2, m2, self.relative),
-                    );
-
-                    if param1 == param2 {
-                        set_value(&mut self.mem, self.pc + 3, m3, 1, self.relative);
-                    } else {
-                        set_value(&mut self.mem, self.pc + 3, m3, 0, self.relative);
-                    }
-                    self.pc += Into::<usize>::into(opcode);
-                }
-                Opcode::AdjustRelative(m1) => {
-                    let param1 = get_value(&self.mem, self.pc + 1, m1, self.relative);
-
-                    self.relative += param1;
-                    self.pc += Into::<usize>::into(opcode);
-                }
-                Opcode::Halt => {
+                RunState::Halted => {
                     self.output = None;
                     break;
                 }
             }
         }
 
-        self.output
+        Ok(self.output)
+    }
+
+    // Executes at most one instruction, looked up in the machine's registry.
+    // Returns None after an ordinary instruction, Output after an output
+    // instruction, and NeedsInput or Halted without executing anything when
+    // the machine can't proceed.
+    pub fn step(&mut self) -> Result<Option<RunState>, IntcodeError> {
+        exec::check_pc(self)?;
+
+        self.dispatch(self.mem.get(self.pc)).map(exec::run_state)
+    }
+
+    // Like step, but reports the executed instruction to `tracer`. The untraced
+    // path is left alone so tracing costs nothing unless it's asked for.
+    pub fn step_traced<T: Tracer>(
+        &mut self,
+        tracer: &mut T,
+    ) -> Result<Option<RunState>, IntcodeError> {
+        let before = self.inspect();
+        let state = self.step()?;
+
+        if let (Some(mut event), None | Some(RunState::Output(_))) = (before, state) {
+            if let Some((addr, value)) = event.write.as_mut() {
+                *value = self.mem.get(*addr);
+            }
+
+            tracer.trace(&event);
+        }
+
+        Ok(state)
+    }
+
+    pub fn resume_traced<T: Tracer>(&mut self, tracer: &mut T) -> Result<RunState, IntcodeError> {
+        loop {
+            if let Some(state) = self.step_traced(tracer)? {
+                return Ok(state);
+            }
+        }
+    }
+
+    // Operands are always read before the result is written, so the values an
+    // instruction sees can be worked out before running it. The written value
+    // is filled in afterwards.
+    fn inspect(&self) -> Option<TraceEvent> {
+        let (instruction, modes) = self.current_instruction().ok()?;
+        let params = (1..=modes.len())
+            .map(|offset| self.mem.get(self.pc + offset))
+            .collect();
+
+        let (read_modes, target) = match (instruction.writes(), modes.split_last()) {
+            (true, Some((&last, rest))) => {
+                (rest, Some(exec::address(self, modes.len(), last).ok()?))
+            }
+            _ => (&modes[..], None),
+        };
+        let reads = read_modes
+            .iter()
+            .enumerate()
+            .map(|(i, &mode)| exec::read(self, i + 1, mode))
+            .collect::<Result<_, _>>()
+            .ok()?;
+
+        Some(TraceEvent {
+            pc: self.pc,
+            mnemonic: instruction.mnemonic().to_string(),
+            modes,
+            params,
+            reads,
+            write: target.map(|addr| (addr, 0)),
+        })
     }
 }
 
-pub fn get_value(mem: &[i64], addr: usize, mode: Mode, relative: i64) -> i64 {
-    match mode {
-        Mode::Immediate => mem[addr],
-        Mode::Position => mem[mem[addr] as usize],
-        Mode::Relative => mem[(mem[addr] + relative) as usize],
+impl Machine for Amp {
+    type Word = i64;
+    type Error = IntcodeError;
+
+    #[inline]
+    fn pc(&self) -> usize {
+        self.pc
+    }
+
+    #[inline]
+    fn set_pc(&mut self, pc: usize) {
+        self.pc = pc;
+    }
+
+    #[inline]
+    fn overflow(&self) -> Overflow {
+        self.overflow
+    }
+
+    #[inline]
+    fn relative(&self) -> &i64 {
+        &self.relative
+    }
+
+    #[inline]
+    fn set_relative(&mut self, relative: i64) -> Result<(), IntcodeError> {
+        self.relative = relative;
+        Ok(())
+    }
+
+    #[inline]
+    fn memory(&self) -> &Memory {
+        &self.mem
+    }
+
+    #[inline]
+    fn store(&mut self, addr: usize, value: i64) {
+        self.mem.set(addr, value);
+        self.cache.invalidate(addr);
+    }
+
+    #[inline]
+    fn input(&mut self) -> Option<i64> {
+        self.input_buffer.pop_front()
     }
 }
 
-pub fn set_value(mem: &mut [i64], addr: usize, mode: Mode, value: i64, relative: i64) {
-    match mode {
-        Mode::Immediate => mem[addr] = value,
-        Mode::Position => mem[mem[addr] as usize] = value,
-        Mode::Relative => mem[(mem[addr] + relative) as usize] = value,
-    };
+#[cfg(test)]
+mod test {
+    use crate::{Amp, IntcodeError, Mode, Opcode, RunState};
+
+    #[test]
+    fn writes_and_reads_far_address() {
+        let program = vec![1101, 5, 6, 1_000_000, 4, 1_000_000, 99];
+        let expected = 11;
+
+        let mut amp = Amp::new(program);
+        let actual = amp.run(None, 0).unwrap().unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn relative_base_reaches_unpadded_memory() {
+        let program = vec![109, 5000, 21101, 3, 4, 0, 204, 0, 99];
+        let expected = 7;
+
+        let mut amp = Amp::new(program);
+        let actual = amp.run(None, 0).unwrap().unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn decodes_opcode_and_modes() {
+        let expected = Opcode::Mul(Mode::Position, Mode::Immediate, Mode::Position);
+
+        let actual = Opcode::decode(1002, 0).unwrap();
+
+        assert_eq!(actual, expected);
+        assert_eq!(usize::from(actual), 4);
+        assert_eq!(actual.encode(), 1002);
+    }
+
+    #[test]
+    fn reports_unknown_opcode() {
+        let mut amp = Amp::new(vec![1101, 1, 1, 5, 42, 0]);
+
+        let actual = amp.run(None, 0);
+
+        assert_eq!(
+            actual,
+            Err(IntcodeError::UnknownOpcode { pc: 4, opcode: 42 })
+        );
+    }
+
+    #[test]
+    fn reports_invalid_mode() {
+        let mut amp = Amp::new(vec![301, 0, 0, 0, 99]);
+
+        let actual = amp.run(None, 0);
+
+        assert_eq!(actual, Err(IntcodeError::InvalidMode { pc: 0, mode: 3 }));
+    }
+
+    #[test]
+    fn reports_immediate_write() {
+        let mut amp = Amp::new(vec![11101, 1, 1, 0, 99]);
+
+        let actual = amp.run(None, 0);
+
+        assert_eq!(actual, Err(IntcodeError::ImmediateWrite { pc: 0 }));
+    }
+
+    #[test]
+    fn reports_negative_address() {
+        let mut amp = Amp::new(vec![4, -3, 99]);
+
+        let actual = amp.run(None, 0);
+
+        assert_eq!(
+            actual,
+            Err(IntcodeError::NegativeAddress { pc: 0, address: -3 })
+        );
+    }
+
+    #[test]
+    fn reports_jump_out_of_range() {
+        let mut amp = Amp::new(vec![1105, 1, -7]);
+
+        let actual = amp.run(None, 0);
+
+        assert_eq!(
+            actual,
+            Err(IntcodeError::PcOutOfRange { pc: 0, target: -7 })
+        );
+    }
+
+    #[test]
+    fn reports_running_off_the_end() {
+        let mut amp = Amp::new(vec![1101, 1, 1, 0]);
+
+        let actual = amp.run(None, 0);
+
+        assert_eq!(actual, Err(IntcodeError::PcOutOfRange { pc: 4, target: 4 }));
+    }
+
+    #[test]
+    fn blocks_on_input_instead_of_inventing_one() {
+        let mut amp = Amp::new(vec![3, 9, 4, 9, 3, 10, 4, 10, 99, 0, 0]);
+
+        assert_eq!(amp.resume(), Ok(RunState::NeedsInput));
+        assert_eq!(amp.resume(), Ok(RunState::NeedsInput));
+
+        amp.push_input(5);
+        assert_eq!(amp.resume(), Ok(RunState::Output(5)));
+        assert_eq!(amp.resume(), Ok(RunState::NeedsInput));
+
+        amp.push_input(6);
+        assert_eq!(amp.resume(), Ok(RunState::Output(6)));
+        assert_eq!(amp.resume(), Ok(RunState::Halted));
+        assert_eq!(amp.resume(), Ok(RunState::Halted));
+    }
+
+    #[test]
+    fn steps_one_instruction_at_a_time() {
+        let mut amp = Amp::new(vec![109, 7, 3, 0, 4, 0, 99]);
+
+        assert_eq!(amp.step(), Ok(None));
+        assert_eq!((amp.pc(), amp.relative_base()), (2, 7));
+        assert_eq!(amp.step(), Ok(Some(RunState::NeedsInput)));
+        assert_eq!(amp.pc(), 2);
+
+        amp.push_input(-4);
+        assert_eq!(amp.step(), Ok(None));
+        assert_eq!(amp.memory().get(0), -4);
+        assert_eq!(amp.step(), Ok(Some(RunState::Output(-4))));
+        assert_eq!(amp.step(), Ok(Some(RunState::Halted)));
+        assert_eq!(amp.pc(), 6);
+    }
 }
@@ -0,0 +1,225 @@
+use crate::image::{self, Image, ImageError};
+use flate2::read::MultiGzDecoder;
+use std::env;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    Image(ImageError),
+    // A binary image that doesn't start at address 0, read as bare words.
+    NonZeroEntry(usize),
+    Malformed {
+        line: usize,
+        column: usize,
+        token: String,
+    },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(error) => write!(f, "{}", error),
+            LoadError::Image(error) => write!(f, "{}", error),
+            LoadError::NonZeroEntry(entry) => {
+                write!(f, "image starts at {}, not 0; load it as an image", entry)
+            }
+            LoadError::Malformed {
+                line,
+                column,
+                token,
+            } if token.is_empty() => {
+                write!(f, "{}:{}: empty word", line, column)
+            }
+            LoadError::Malformed {
+                line,
+                column,
+                token,
+            } => write!(f, "{}:{}: malformed word `{}`", line, column, token),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+impl From<io::Error> for LoadError {
+    fn from(error: io::Error) -> Self {
+        LoadError::Io(error)
+    }
+}
+
+// Words may be separated by commas, whitespace or both; a trailing comma is
+// fine, but a comma with no word before it is an empty word. Positions in
+// errors are 1-based.
+pub fn parse(text: &str) -> Result<Vec<i64>, LoadError> {
+    let mut program = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let mut start = None;
+        // Whether a comma here would close a field with nothing in it.
+        let mut empty = true;
+        let malformed = |start: usize, token: &str| LoadError::Malformed {
+            line: i + 1,
+            column: line[..start].chars().count() + 1,
+            token: token.to_string(),
+        };
+
+        for (column, c) in line.char_indices().chain(Some((line.len(), '\n'))) {
+            if c == ',' || c.is_whitespace() {
+                if let Some(start) = start.take() {
+                    let token = &line[start..column];
+                    let word = token.parse().map_err(|_| malformed(start, token))?;
+                    program.push(word);
+                }
+                if c == ',' {
+                    if empty {
+                        return Err(malformed(column, ""));
+                    }
+                    empty = true;
+                }
+            } else if start.is_none() {
+                start = Some(column);
+                empty = false;
+            }
+        }
+    }
+
+    Ok(program)
+}
+
+// Reads a program as text, transparently decompressing gzip input and
+// accepting binary images. Images that start anywhere but address 0 are
+// rejected, since the words alone would run from the wrong place; use
+// `image` for those.
+pub fn read<R: Read>(reader: R) -> Result<Vec<i64>, LoadError> {
+    let image = image(reader)?;
+    if image.entry != 0 {
+        return Err(LoadError::NonZeroEntry(image.entry));
+    }
+
+    Ok(image.words)
+}
+
+// Like `read`, but keeps everything a binary image carries. Text programs
+// become an image that starts at 0 with no symbols.
+pub fn image<R: Read>(mut reader: R) -> Result<Image, LoadError> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+
+    let mut text = String::new();
+    if bytes.starts_with(&image::MAGIC) {
+        return Image::from_bytes(&bytes).map_err(LoadError::Image);
+    } else if bytes.starts_with(&GZIP_MAGIC) {
+        MultiGzDecoder::new(&bytes[..]).read_to_string(&mut text)?;
+    } else {
+        text = String::from_utf8(bytes)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+    }
+
+    parse(&text).map(Image::new)
+}
+
+// A path of `-` reads standard input.
+pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Vec<i64>, LoadError> {
+    if path.as_ref() == Path::new("-") {
+        return from_stdin();
+    }
+
+    read(File::open(path)?)
+}
+
+pub fn from_stdin() -> Result<Vec<i64>, LoadError> {
+    read(io::stdin().lock())
+}
+
+// Loads the program named by the first command-line argument, falling back
+// to `default` when there isn't one.
+pub fn from_args_or(default: &str) -> Result<Vec<i64>, LoadError> {
+    match env::args_os().nth(1) {
+        Some(path) => from_path(path),
+        None => parse(default),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::image::Image;
+    use crate::load::{image, parse, read, LoadError};
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    #[test]
+    fn tolerates_whitespace_and_newlines() {
+        let expected = vec![1, 0, -3, 4, 99];
+
+        let actual = parse(" 1,0,\n  -3 , 4\n99,\n").unwrap();
+
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn reports_position_of_malformed_word() {
+        match parse("1,2,3\n4, 5x,6") {
+            Err(LoadError::Malformed {
+                line,
+                column,
+                token,
+            }) => assert_eq!((line, column, token.as_str()), (2, 4, "5x")),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_empty_words_between_commas() {
+        for text in &["1,,2", "1, ,2", ",1,2", "1,2,,\n"] {
+            assert!(
+                matches!(parse(text), Err(LoadError::Malformed { ref token, .. }) if token.is_empty()),
+                "{:?} parsed",
+                text
+            );
+        }
+        match parse("1,2\n3,,4") {
+            Err(LoadError::Malformed { line, column, .. }) => assert_eq!((line, column), (2, 3)),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(parse("1,2, \n3 ,").unwrap(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn reads_gzip_compressed_programs() {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"104,42,99\n").unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        assert_eq!(read(&compressed[..]).unwrap(), vec![104, 42, 99]);
+        assert_eq!(read(&b"104,42,99"[..]).unwrap(), vec![104, 42, 99]);
+    }
+
+    #[test]
+    fn reads_binary_images() {
+        let bytes = Image::new(vec![104, -42, 99]).to_bytes();
+
+        assert_eq!(read(&bytes[..]).unwrap(), vec![104, -42, 99]);
+    }
+
+    #[test]
+    fn keeps_the_entry_point_of_images() {
+        let mut original = Image::new(vec![99, 104, 7, 99]);
+        original.entry = 1;
+        let bytes = original.to_bytes();
+
+        assert_eq!(image(&bytes[..]).unwrap(), original);
+        match read(&bytes[..]) {
+            Err(LoadError::NonZeroEntry(1)) => {}
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert_eq!(image(&b"104,42,99"[..]).unwrap().entry, 0);
+    }
+}
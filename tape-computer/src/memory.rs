@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// Addresses below this limit live in fixed-size pages that are allocated on
+// demand; anything higher is stored sparsely so a single far write doesn't
+// allocate gigabytes of zeroes.
+const DENSE_LIMIT: usize = 1 << 16;
+
+// Pages are shared between clones and only copied when one of them writes,
+// so forking a machine costs one pointer per page.
+const PAGE_BITS: usize = 9;
+const PAGE_SIZE: usize = 1 << PAGE_BITS;
+
+type Page<W> = [W; PAGE_SIZE];
+
+// Words default to i64, the width `Amp` runs on; `WordAmp` and `Symbolic`
+// store their own.
+#[derive(Debug, Clone, Default)]
+pub struct Memory<W = i64> {
+    pages: Vec<Arc<Page<W>>>,
+    dense_len: usize,
+    sparse: Arc<HashMap<usize, W>>,
+    sparse_end: usize,
+}
+
+impl<W: Clone + Default> Memory<W> {
+    pub fn new(program: Vec<W>) -> Self {
+        let pages = program
+            .chunks(PAGE_SIZE)
+            .map(|chunk| {
+                let mut page = blank();
+                page[..chunk.len()].clone_from_slice(chunk);
+                Arc::new(page)
+            })
+            .collect();
+
+        Self {
+            pages,
+            dense_len: program.len(),
+            sparse: Arc::default(),
+            sparse_end: 0,
+        }
+    }
+
+    #[inline]
+    pub fn get(&self, addr: usize) -> W {
+        match self.pages.get(addr >> PAGE_BITS) {
+            Some(page) => page[addr & (PAGE_SIZE - 1)].clone(),
+            None => self.sparse.get(&addr).cloned().unwrap_or_default(),
+        }
+    }
+
+    pub fn set(&mut self, addr: usize, value: W) {
+        let page = addr >> PAGE_BITS;
+
+        if page < self.pages.len() || addr < DENSE_LIMIT {
+            if page >= self.pages.len() {
+                self.pages.resize_with(page + 1, || Arc::new(blank()));
+            }
+
+            Arc::make_mut(&mut self.pages[page])[addr & (PAGE_SIZE - 1)] = value;
+            self.dense_len = self.dense_len.max(addr + 1);
+        } else {
+            Arc::make_mut(&mut self.sparse).insert(addr, value);
+            self.sparse_end = self.sparse_end.max(addr + 1);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.dense_len.max(self.sparse_end)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Every word up to the highest address touched, sparse region included.
+    pub fn to_vec(&self) -> Vec<W> {
+        let mut image = self.dense();
+        image.resize(self.len(), W::default());
+
+        for (&addr, value) in self.sparse.iter() {
+            image[addr] = value.clone();
+        }
+
+        image
+    }
+
+    pub(crate) fn dense(&self) -> Vec<W> {
+        self.pages
+            .iter()
+            .flat_map(|page| page.iter().cloned())
+            .take(self.dense_len)
+            .collect()
+    }
+
+    pub(crate) fn sparse(&self) -> Vec<(usize, W)> {
+        let mut entries = self
+            .sparse
+            .iter()
+            .map(|(&addr, value)| (addr, value.clone()))
+            .collect::<Vec<_>>();
+        entries.sort_unstable_by_key(|&(addr, _)| addr);
+
+        entries
+    }
+}
+
+fn blank<W: Default>() -> Page<W> {
+    std::array::from_fn(|_| W::default())
+}
+
+impl<W: Clone + Default> From<Vec<W>> for Memory<W> {
+    fn from(program: Vec<W>) -> Self {
+        Memory::new(program)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::memory::Memory;
+    use std::sync::Arc;
+
+    #[test]
+    fn reads_zero_past_program() {
+        let memory = Memory::new(vec![1, 2, 3]);
+
+        assert_eq!(memory.get(3), 0);
+        assert_eq!(memory.get(1_000_000), 0);
+    }
+
+    #[test]
+    fn grows_dense_region_on_write() {
+        let mut memory = Memory::new(vec![1, 2, 3]);
+
+        memory.set(100, 42);
+
+        assert_eq!(memory.get(100), 42);
+        assert_eq!(memory.get(99), 0);
+        assert_eq!(memory.len(), 101);
+    }
+
+    #[test]
+    fn writes_far_address_sparsely() {
+        let mut memory = Memory::new(vec![1, 2, 3]);
+
+        memory.set(1_000_000, 7);
+
+        assert_eq!(memory.get(1_000_000), 7);
+        assert_eq!(memory.len(), 1_000_001);
+        assert_eq!(memory.dense_len, 3);
+
+        memory.set(500_000, 8);
+        assert_eq!(memory.len(), 1_000_001);
+    }
+
+    #[test]
+    fn clones_share_pages_until_written() {
+        let mut original = Memory::new((0..2000).collect());
+        let mut copy = original.clone();
+
+        copy.set(1500, -1);
+        original.set(1_000_000, 5);
+
+        assert_eq!(original.get(1500), 1500);
+        assert_eq!(copy.get(1500), -1);
+        assert_eq!(copy.get(1_000_000), 0);
+        assert!(Arc::ptr_eq(&original.pages[0], &copy.pages[0]));
+        assert!(!Arc::ptr_eq(&original.pages[2], &copy.pages[2]));
+    }
+}
@@ -0,0 +1,525 @@
+use crate::{Amp, IntcodeError, RunState};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Routing {
+    // Each output goes to the next machine, wrapping back to the first.
+    Ring,
+    // Each output goes to every other machine.
+    Broadcast,
+    // Outputs are grouped into `[address, payload...]` packets. Packets for
+    // the monitor address are kept by the network instead of delivered.
+    Addressed {
+        payload: usize,
+        monitor: Option<usize>,
+    },
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Stop {
+    Halted,
+    Idle,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct NetworkError {
+    pub machine: usize,
+    pub error: IntcodeError,
+}
+
+impl fmt::Display for NetworkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "machine {}: {}", self.machine, self.error)
+    }
+}
+
+impl Error for NetworkError {}
+
+#[derive(Debug, Clone)]
+struct Router {
+    routing: Routing,
+    queues: Vec<VecDeque<i64>>,
+    partial: Vec<Vec<i64>>,
+    last_output: Vec<Option<i64>>,
+    monitor_log: Vec<Vec<i64>>,
+}
+
+impl Router {
+    fn route(&mut self, from: usize, value: i64) {
+        self.last_output[from] = Some(value);
+        let machines = self.queues.len();
+
+        match self.routing {
+            Routing::Ring => self.queues[(from + 1) % machines].push_back(value),
+            Routing::Broadcast => {
+                for (to, queue) in self.queues.iter_mut().enumerate() {
+                    if to != from {
+                        queue.push_back(value);
+                    }
+                }
+            }
+            Routing::Addressed { payload, monitor } => {
+                self.partial[from].push(value);
+                if self.partial[from].len() <= payload {
+                    return;
+                }
+
+                let packet = std::mem::take(&mut self.partial[from]);
+                let to = packet[0];
+                if to >= 0 && (to as usize) < machines {
+                    self.queues[to as usize].extend(&packet[1..]);
+                } else if to >= 0 && Some(to as usize) == monitor {
+                    self.monitor_log.push(packet[1..].to_vec());
+                }
+            }
+        }
+    }
+}
+
+enum Event {
+    Output(usize, i64),
+    Blocked(usize, usize),
+    Halted(usize),
+    Fault(usize, IntcodeError),
+}
+
+enum Message {
+    Value(i64),
+    Stop,
+}
+
+#[derive(Debug, Clone)]
+pub struct Network {
+    amps: Vec<Amp>,
+    halted: Vec<bool>,
+    idle_input: Option<i64>,
+    router: Router,
+}
+
+impl Network {
+    pub fn new(routing: Routing) -> Self {
+        Self {
+            amps: Vec::new(),
+            halted: Vec::new(),
+            idle_input: None,
+            router: Router {
+                routing,
+                queues: Vec::new(),
+                partial: Vec::new(),
+                last_output: Vec::new(),
+                monitor_log: Vec::new(),
+            },
+        }
+    }
+
+    // Adds a machine and returns its address.
+    pub fn add(&mut self, amp: Amp) -> usize {
+        self.amps.push(amp);
+        self.halted.push(false);
+        self.router.queues.push(VecDeque::new());
+        self.router.partial.push(Vec::new());
+        self.router.last_output.push(None);
+
+        self.amps.len() - 1
+    }
+
+    // Value fed to a machine that asks for input while its queue is empty.
+    // Without one, such a machine simply waits.
+    pub fn set_idle_input(&mut self, value: Option<i64>) {
+        self.idle_input = value;
+    }
+
+    pub fn len(&self) -> usize {
+        self.amps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.amps.is_empty()
+    }
+
+    pub fn machine(&self, addr: usize) -> &Amp {
+        &self.amps[addr]
+    }
+
+    pub fn send(&mut self, to: usize, value: i64) {
+        self.router.queues[to].push_back(value);
+    }
+
+    pub fn last_output(&self, addr: usize) -> Option<i64> {
+        self.router.last_output[addr]
+    }
+
+    // Every packet sent to the monitor address, oldest first.
+    pub fn monitor_log(&self) -> &[Vec<i64>] {
+        &self.router.monitor_log
+    }
+
+    // Resends the last packet the monitor received to machine 0, the way a
+    // NAT wakes an idle network.
+    pub fn wake(&mut self) -> Option<Vec<i64>> {
+        let packet = self.router.monitor_log.last()?.clone();
+        self.router.queues[0].extend(&packet);
+
+        Some(packet)
+    }
+
+    // Runs each machine in address order until it blocks, repeating until
+    // every machine has halted or a whole round passes without any traffic.
+    pub fn run(&mut self) -> Result<Stop, NetworkError> {
+        loop {
+            let mut active = false;
+
+            for machine in 0..self.amps.len() {
+                if self.halted[machine] {
+                    continue;
+                }
+
+                let amp = &mut self.amps[machine];
+                let queue = &mut self.router.queues[machine];
+                if !queue.is_empty() {
+                    active = true;
+                    amp.extend_input(queue.drain(..));
+                } else if let Some(idle) = self.idle_input {
+                    if amp.pending_input().is_empty() {
+                        amp.push_input(idle);
+                    }
+                }
+
+                let mut outputs = Vec::new();
+                loop {
+                    match amp.resume_fast() {
+                        Ok(RunState::Output(value)) => outputs.push(value),
+                        Ok(RunState::NeedsInput) => break,
+                        Ok(RunState::Halted) => {
+                            self.halted[machine] = true;
+                            break;
+                        }
+                        Err(error) => return Err(NetworkError { machine, error }),
+                    }
+                }
+
+                active |= !outputs.is_empty();
+                for value in outputs {
+                    self.router.route(machine, value);
+                }
+            }
+
+            if self.halted.iter().all(|&halted| halted) {
+                return Ok(Stop::Halted);
+            } else if !active {
+                return Ok(Stop::Idle);
+            }
+        }
+    }
+
+    // Same as `run`, but every machine gets its own thread. The network is
+    // idle once every live machine is waiting with nothing left to read, and,
+    // with an idle value set, has read it since without answering.
+    pub fn run_threaded(&mut self) -> Result<Stop, NetworkError> {
+        let Network {
+            amps,
+            halted,
+            idle_input,
+            router,
+        } = self;
+        let idle_input = *idle_input;
+        let (events, inbox) = mpsc::channel();
+
+        thread::scope(|scope| {
+            let mut inputs = Vec::new();
+            for (machine, amp) in amps.iter_mut().enumerate() {
+                let (input, receiver) = mpsc::channel();
+                inputs.push(input);
+
+                if !halted[machine] {
+                    let events = events.clone();
+                    scope.spawn(move || run_machine(machine, amp, receiver, events, idle_input));
+                }
+            }
+            drop(events);
+
+            let stop = route_events(&inbox, &inputs, halted, router);
+
+            for input in &inputs {
+                input.send(Message::Stop).ok();
+            }
+
+            stop
+        })
+    }
+}
+
+fn route_events(
+    inbox: &Receiver<Event>,
+    inputs: &[Sender<Message>],
+    halted: &mut [bool],
+    router: &mut Router,
+) -> Result<Stop, NetworkError> {
+    let mut sent = vec![0; inputs.len()];
+    let mut waiting = vec![false; inputs.len()];
+
+    loop {
+        for (machine, queue) in router.queues.iter_mut().enumerate() {
+            if halted[machine] {
+                continue;
+            }
+
+            for value in queue.drain(..) {
+                inputs[machine].send(Message::Value(value)).ok();
+                sent[machine] += 1;
+                waiting[machine] = false;
+            }
+        }
+
+        if halted.iter().all(|&halted| halted) {
+            return Ok(Stop::Halted);
+        } else if (0..inputs.len()).all(|machine| halted[machine] || waiting[machine]) {
+            return Ok(Stop::Idle);
+        }
+
+        match inbox.recv() {
+            Ok(Event::Output(machine, value)) => {
+                waiting[machine] = false;
+                router.route(machine, value);
+            }
+            Ok(Event::Blocked(machine, consumed)) => waiting[machine] = consumed == sent[machine],
+            Ok(Event::Halted(machine)) => halted[machine] = true,
+            Ok(Event::Fault(machine, error)) => {
+                halted[machine] = true;
+                return Err(NetworkError { machine, error });
+            }
+            Err(_) => return Ok(Stop::Halted),
+        }
+    }
+}
+
+// How long a machine with an idle value waits for a message before reading
+// the idle value again.
+const IDLE_WAIT: Duration = Duration::from_millis(1);
+
+fn run_machine(
+    machine: usize,
+    amp: &mut Amp,
+    input: Receiver<Message>,
+    events: Sender<Event>,
+    idle_input: Option<i64>,
+) {
+    let mut consumed = 0;
+    // Set when the machine reads the idle value, cleared by any traffic.
+    let mut quiet = false;
+
+    loop {
+        match amp.resume_fast() {
+            Ok(RunState::Output(value)) => {
+                quiet = false;
+                events.send(Event::Output(machine, value)).ok();
+            }
+            Ok(RunState::Halted) => {
+                events.send(Event::Halted(machine)).ok();
+                return;
+            }
+            Err(error) => {
+                events.send(Event::Fault(machine, error)).ok();
+                return;
+            }
+            Ok(RunState::NeedsInput) => {
+                let message = match input.try_recv() {
+                    Ok(message) => Some(message),
+                    Err(TryRecvError::Disconnected) => None,
+                    Err(TryRecvError::Empty) => match idle_input {
+                        None => {
+                            events.send(Event::Blocked(machine, consumed)).ok();
+                            input.recv().ok()
+                        }
+                        // Only a machine that has been through a whole round
+                        // on the idle value without output counts as idle.
+                        // It says so after every such round, and gives the
+                        // others a moment to send it something before the
+                        // next.
+                        Some(idle) => {
+                            let message = if quiet {
+                                events.send(Event::Blocked(machine, consumed)).ok();
+                                match input.recv_timeout(IDLE_WAIT) {
+                                    Ok(message) => Some(message),
+                                    Err(RecvTimeoutError::Timeout) => None,
+                                    Err(RecvTimeoutError::Disconnected) => return,
+                                }
+                            } else {
+                                None
+                            };
+
+                            if message.is_none() {
+                                amp.push_input(idle);
+                                quiet = true;
+                                continue;
+                            }
+
+                            message
+                        }
+                    },
+                };
+
+                match message {
+                    Some(Message::Value(value)) => {
+                        consumed += 1;
+                        quiet = false;
+                        amp.push_input(value);
+                    }
+                    Some(Message::Stop) | None => return,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::asm::{assemble, assemble_with_labels};
+    use crate::fixtures::FEEDBACK;
+    use crate::network::{Network, Routing, Stop};
+    use crate::Amp;
+
+    fn feedback_loop(phases: &[i64]) -> Network {
+        let mut network = Network::new(Routing::Ring);
+        for &phase in phases {
+            let mut amp = Amp::new(FEEDBACK.to_vec());
+            amp.push_input(phase);
+            network.add(amp);
+        }
+        network.send(0, 0);
+
+        network
+    }
+
+    #[test]
+    fn runs_amplifier_feedback_loop() {
+        let mut network = feedback_loop(&[9, 8, 7, 6, 5]);
+
+        assert_eq!(network.run(), Ok(Stop::Halted));
+        assert_eq!(network.last_output(4), Some(139629729));
+    }
+
+    #[test]
+    fn threads_agree_with_round_robin() {
+        let mut network = feedback_loop(&[9, 8, 7, 6, 5]);
+
+        assert_eq!(network.run_threaded(), Ok(Stop::Halted));
+        assert_eq!(network.last_output(4), Some(139629729));
+    }
+
+    #[test]
+    fn broadcasts_to_every_other_machine() {
+        // Sends its input once, then sums whatever it receives.
+        let (program, labels) = assemble_with_labels(
+            "
+                    in -> [x]
+                    out [x]
+            loop:   in -> [x]
+                    add [x], [sum] -> [sum]
+                    jt #1, #loop
+            x:      .data 0
+            sum:    .data 0
+            ",
+        )
+        .unwrap();
+
+        let mut network = Network::new(Routing::Broadcast);
+        for value in 1..=3 {
+            let mut amp = Amp::new(program.clone());
+            amp.push_input(value * 10);
+            network.add(amp);
+        }
+
+        assert_eq!(network.run(), Ok(Stop::Idle));
+        let sums = (0..network.len())
+            .map(|addr| network.machine(addr).memory().get(labels["sum"]))
+            .collect::<Vec<_>>();
+        assert_eq!(sums, vec![50, 40, 30]);
+    }
+
+    #[test]
+    fn waits_for_answers_to_the_idle_value() {
+        // Forwards the first value it reads, the idle value, to the monitor.
+        let program = assemble(
+            "
+                    in -> [x]
+                    out #255
+                    out [x]
+            loop:   in -> [x]
+                    jt #1, #loop
+            x:      .data 0
+            ",
+        )
+        .unwrap();
+
+        for _ in 0..20 {
+            let mut network = Network::new(Routing::Addressed {
+                payload: 1,
+                monitor: Some(255),
+            });
+            network.add(Amp::new(program.clone()));
+            network.add(Amp::new(program.clone()));
+            network.set_idle_input(Some(-1));
+
+            assert_eq!(network.run_threaded(), Ok(Stop::Idle));
+            assert_eq!(network.monitor_log(), &[vec![-1], vec![-1]]);
+        }
+    }
+
+    #[test]
+    fn delivers_packets_and_reports_idle_to_monitor() {
+        // Machine 0 forwards every value it reads to the monitor at 255;
+        // machine 1 sends it one packet.
+        let relay = assemble(
+            "
+            loop:   in -> [x]
+                    eq [x], #-1 -> [idle]
+                    jt [idle], #loop
+                    out #255
+                    out [x]
+                    jt #1, #loop
+            x:      .data 0
+            idle:   .data 0
+            ",
+        )
+        .unwrap();
+        let sender = assemble(
+            "
+                    out #0
+                    out #42
+            wait:   in -> [x]
+                    jt #1, #wait
+            x:      .data 0
+            ",
+        )
+        .unwrap();
+
+        for threaded in [false, true].iter() {
+            let mut network = Network::new(Routing::Addressed {
+                payload: 1,
+                monitor: Some(255),
+            });
+            network.add(Amp::new(relay.clone()));
+            network.add(Amp::new(sender.clone()));
+            network.set_idle_input(Some(-1));
+
+            let stop = if *threaded {
+                network.run_threaded()
+            } else {
+                network.run()
+            };
+
+            assert_eq!(stop, Ok(Stop::Idle));
+            assert_eq!(network.monitor_log(), &[vec![42]]);
+
+            assert_eq!(network.wake(), Some(vec![42]));
+            assert_eq!(network.run(), Ok(Stop::Idle));
+            assert_eq!(network.monitor_log(), &[vec![42], vec![42]]);
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use crate::word::Word;
+use crate::IntcodeError;
+
+// How `Amp` handles arithmetic that doesn't fit in an i64, and a fixed-width
+// `WordAmp` arithmetic that doesn't fit its word. This covers add, mul,
+// relative base adjustments and relative-mode address calculation.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum Overflow {
+    #[default]
+    Checked,
+    Wrapping,
+    Saturating,
+}
+
+impl Overflow {
+    #[inline]
+    pub fn add(self, a: i64, b: i64, pc: usize) -> Result<i64, IntcodeError> {
+        Word::add(&a, &b, self, pc)
+    }
+
+    #[inline]
+    pub fn mul(self, a: i64, b: i64, pc: usize) -> Result<i64, IntcodeError> {
+        Word::mul(&a, &b, self, pc)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fixtures::SQUARE;
+    use crate::{Amp, IntcodeError, Overflow, RunState};
+
+    fn square(value: i64, overflow: Overflow) -> Result<RunState, IntcodeError> {
+        let mut amp = Amp::new(SQUARE.to_vec());
+        amp.set_overflow(overflow);
+        amp.push_input(value);
+
+        amp.resume()
+    }
+
+    #[test]
+    fn checked_overflow_reports_pc() {
+        assert_eq!(
+            square(1 << 32, Overflow::Checked),
+            Err(IntcodeError::Overflow { pc: 2 })
+        );
+        assert_eq!(
+            square(1 << 31, Overflow::Checked),
+            Ok(RunState::Output(1 << 62))
+        );
+    }
+
+    #[test]
+    fn wrapping_and_saturating_overflow() {
+        assert_eq!(
+            square((1 << 32) + 1, Overflow::Wrapping),
+            Ok(RunState::Output((1 << 33) + 1))
+        );
+        assert_eq!(
+            square(-(1 << 40), Overflow::Saturating),
+            Ok(RunState::Output(i64::MAX))
+        );
+    }
+
+    #[test]
+    fn relative_address_overflow_is_checked() {
+        let program = vec![109, i64::MAX, 204, 1, 99];
+
+        let mut amp = Amp::new(program.clone());
+        assert_eq!(amp.resume(), Err(IntcodeError::Overflow { pc: 2 }));
+
+        let mut amp = Amp::new(program);
+        amp.set_overflow(Overflow::Wrapping);
+        assert_eq!(
+            amp.resume(),
+            Err(IntcodeError::NegativeAddress {
+                pc: 2,
+                address: i64::MIN
+            })
+        );
+    }
+}
@@ -0,0 +1,194 @@
+use crate::network::{Network, NetworkError, Routing};
+use crate::{Amp, RunState};
+use itertools::Itertools;
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Wiring {
+    // The signal passes through each amplifier once.
+    Serial,
+    // The last amplifier feeds the first until they all halt.
+    Feedback,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Best {
+    pub phases: Vec<i64>,
+    pub signal: i64,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Progress {
+    pub evaluated: usize,
+    // None when there are more orderings than a usize can count.
+    pub total: Option<usize>,
+    pub phases: Vec<i64>,
+    pub signal: Option<i64>,
+}
+
+// Signal produced by one chain of amplifiers fed an initial signal of 0, or
+// None if the chain never produced one.
+pub fn evaluate(
+    program: &[i64],
+    phases: &[i64],
+    wiring: Wiring,
+) -> Result<Option<i64>, NetworkError> {
+    match wiring {
+        Wiring::Serial => {
+            let mut signal = 0;
+            for (machine, &phase) in phases.iter().enumerate() {
+                let mut amp = Amp::new(program.to_vec());
+                amp.push_input(phase);
+                amp.push_input(signal);
+
+                signal = match amp.resume_fast() {
+                    Ok(RunState::Output(value)) => value,
+                    Ok(_) => return Ok(None),
+                    Err(error) => return Err(NetworkError { machine, error }),
+                };
+            }
+
+            Ok(Some(signal))
+        }
+        Wiring::Feedback => {
+            let mut network = Network::new(Routing::Ring);
+            for &phase in phases {
+                let mut amp = Amp::new(program.to_vec());
+                amp.push_input(phase);
+                network.add(amp);
+            }
+            network.send(0, 0);
+
+            network.run()?;
+
+            Ok(network.last_output(phases.len() - 1))
+        }
+    }
+}
+
+pub fn search(
+    program: &[i64],
+    settings: &[i64],
+    length: usize,
+    wiring: Wiring,
+) -> Result<Option<Best>, NetworkError> {
+    search_with_progress(program, settings, length, wiring, |_| {})
+}
+
+// Tries every ordering of `length` distinct settings in parallel, reporting
+// each one as it finishes. Ties go to the lexicographically smallest ordering.
+pub fn search_with_progress<F>(
+    program: &[i64],
+    settings: &[i64],
+    length: usize,
+    wiring: Wiring,
+    progress: F,
+) -> Result<Option<Best>, NetworkError>
+where
+    F: Fn(Progress) + Sync,
+{
+    if length == 0 || length > settings.len() {
+        return Ok(None);
+    }
+
+    let total = orderings(settings.len(), length);
+    let evaluated = AtomicUsize::new(0);
+
+    settings
+        .iter()
+        .copied()
+        .permutations(length)
+        .par_bridge()
+        .map(|phases| {
+            let signal = evaluate(program, &phases, wiring)?;
+
+            progress(Progress {
+                evaluated: evaluated.fetch_add(1, Ordering::Relaxed) + 1,
+                total,
+                phases: phases.clone(),
+                signal,
+            });
+
+            Ok(signal.map(|signal| Best { phases, signal }))
+        })
+        .reduce_with(|a, b| Ok(better(a?, b?)))
+        .unwrap_or(Ok(None))
+}
+
+// How many ways there are to pick `length` of `count` settings in order.
+fn orderings(count: usize, length: usize) -> Option<usize> {
+    (count - length + 1..=count).try_fold(1_usize, |total, n| total.checked_mul(n))
+}
+
+fn better(a: Option<Best>, b: Option<Best>) -> Option<Best> {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            if (b.signal, &a.phases) > (a.signal, &b.phases) {
+                Some(b)
+            } else {
+                Some(a)
+            }
+        }
+        (a, b) => a.or(b),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fixtures::FEEDBACK;
+    use crate::phase::{orderings, search, search_with_progress, Best, Wiring};
+    use std::sync::Mutex;
+
+    const SERIAL: [i64; 17] = [
+        3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
+    ];
+
+    #[test]
+    fn finds_best_serial_ordering() {
+        let expected = Best {
+            phases: vec![4, 3, 2, 1, 0],
+            signal: 43210,
+        };
+
+        let actual = search(&SERIAL, &[0, 1, 2, 3, 4], 5, Wiring::Serial).unwrap();
+
+        assert_eq!(actual, Some(expected));
+    }
+
+    #[test]
+    fn finds_best_feedback_ordering() {
+        let expected = Best {
+            phases: vec![9, 8, 7, 6, 5],
+            signal: 139629729,
+        };
+
+        let actual = search(&FEEDBACK, &[5, 6, 7, 8, 9], 5, Wiring::Feedback).unwrap();
+
+        assert_eq!(actual, Some(expected));
+    }
+
+    #[test]
+    fn reports_every_ordering() {
+        let seen = Mutex::new(Vec::new());
+
+        let best = search_with_progress(&SERIAL, &[0, 1, 2, 3], 3, Wiring::Serial, |progress| {
+            assert_eq!(progress.total, Some(24));
+            seen.lock().unwrap().push(progress.evaluated);
+        })
+        .unwrap();
+
+        let mut seen = seen.into_inner().unwrap();
+        seen.sort_unstable();
+        assert_eq!(seen, (1..=24).collect::<Vec<_>>());
+        assert_eq!(best.unwrap().phases, vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn counts_orderings_until_they_overflow() {
+        assert_eq!(orderings(5, 5), Some(120));
+        assert_eq!(orderings(20, 20), Some(2_432_902_008_176_640_000));
+        assert_eq!(orderings(21, 21), None);
+        assert_eq!(orderings(30, 2), Some(870));
+    }
+}
@@ -0,0 +1,288 @@
+use crate::{Amp, Memory};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+const HEADER: &str = "intcode-snapshot 1";
+
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub pc: usize,
+    pub relative_base: i64,
+    pub memory: Memory,
+    pub input: VecDeque<i64>,
+    pub output: Option<i64>,
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    MissingHeader,
+    Malformed { line: usize, text: String },
+    MissingField(&'static str),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::Io(error) => write!(f, "{}", error),
+            SnapshotError::MissingHeader => write!(f, "not an intcode snapshot"),
+            SnapshotError::Malformed { line, text } => {
+                write!(f, "line {}: malformed entry `{}`", line, text)
+            }
+            SnapshotError::MissingField(field) => write!(f, "missing `{}` entry", field),
+        }
+    }
+}
+
+impl Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(error: io::Error) -> Self {
+        SnapshotError::Io(error)
+    }
+}
+
+impl Snapshot {
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), SnapshotError> {
+        fs::write(path, self.to_string())?;
+
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, SnapshotError> {
+        fs::read_to_string(path)?.parse()
+    }
+}
+
+fn join<T: ToString>(values: impl IntoIterator<Item = T>) -> String {
+    values
+        .into_iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// A line-oriented text format: one `key value` entry per line, with lists
+// written comma-separated like the puzzle inputs.
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        writeln!(f, "pc {}", self.pc)?;
+        writeln!(f, "relative {}", self.relative_base)?;
+        match self.output {
+            Some(value) => writeln!(f, "output {}", value)?,
+            None => writeln!(f, "output -")?,
+        }
+        writeln!(f, "input {}", join(&self.input))?;
+        writeln!(f, "memory {}", join(self.memory.dense()))?;
+
+        let sparse = self.memory.sparse();
+        if !sparse.is_empty() {
+            let entries = sparse
+                .into_iter()
+                .map(|(addr, value)| format!("{}={}", addr, value));
+            writeln!(f, "sparse {}", join(entries))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Snapshot {
+    type Err = SnapshotError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut lines = text.lines().enumerate();
+        match lines.next() {
+            Some((_, header)) if header.trim() == HEADER => {}
+            _ => return Err(SnapshotError::MissingHeader),
+        }
+
+        let mut pc = None;
+        let mut relative_base = None;
+        let mut memory = None;
+        let mut input = VecDeque::new();
+        let mut output = None;
+        let mut sparse = Vec::new();
+
+        for (i, text) in lines {
+            let entry = text.trim();
+            if entry.is_empty() {
+                continue;
+            }
+
+            let malformed = || SnapshotError::Malformed {
+                line: i + 1,
+                text: entry.to_string(),
+            };
+            let (key, value) = match entry.find(' ') {
+                Some(split) => (&entry[..split], entry[split + 1..].trim()),
+                None => (entry, ""),
+            };
+            let list = |value: &str| -> Result<Vec<i64>, SnapshotError> {
+                value
+                    .split(',')
+                    .filter(|word| !word.trim().is_empty())
+                    .map(|word| word.trim().parse().map_err(|_| malformed()))
+                    .collect()
+            };
+
+            match key {
+                "pc" => pc = Some(value.parse().map_err(|_| malformed())?),
+                "relative" => relative_base = Some(value.parse().map_err(|_| malformed())?),
+                "output" if value == "-" => output = None,
+                "output" => output = Some(value.parse().map_err(|_| malformed())?),
+                "input" => input = list(value)?.into(),
+                "memory" => memory = Some(list(value)?),
+                "sparse" => {
+                    for pair in value.split(',') {
+                        let mut parts = pair.trim().splitn(2, '=');
+                        // No machine address is beyond an i64.
+                        let addr = parts
+                            .next()
+                            .and_then(|addr| addr.parse::<usize>().ok())
+                            .filter(|&addr| addr <= i64::MAX as usize);
+                        let word = parts.next().and_then(|word| word.parse().ok());
+                        match (addr, word) {
+                            (Some(addr), Some(word)) => sparse.push((addr, word)),
+                            _ => return Err(malformed()),
+                        }
+                    }
+                }
+                _ => return Err(malformed()),
+            }
+        }
+
+        let mut memory = Memory::new(memory.ok_or(SnapshotError::MissingField("memory"))?);
+        for (addr, value) in sparse {
+            memory.set(addr, value);
+        }
+
+        Ok(Snapshot {
+            pc: pc.ok_or(SnapshotError::MissingField("pc"))?,
+            relative_base: relative_base.ok_or(SnapshotError::MissingField("relative"))?,
+            memory,
+            input,
+            output,
+        })
+    }
+}
+
+impl Amp {
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            pc: self.pc,
+            relative_base: self.relative,
+            memory: self.mem.clone(),
+            input: self.input_buffer.clone(),
+            output: self.output,
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.pc = snapshot.pc;
+        self.relative = snapshot.relative_base;
+        self.mem = snapshot.memory.clone();
+        self.input_buffer = snapshot.input.clone();
+        self.output = snapshot.output;
+        self.cache.clear();
+    }
+
+    // A copy that shares memory pages with this machine until either writes.
+    pub fn fork(&self) -> Amp {
+        self.clone()
+    }
+}
+
+impl From<Snapshot> for Amp {
+    fn from(snapshot: Snapshot) -> Self {
+        let mut amp = Amp::new(vec![]);
+        amp.restore(&snapshot);
+
+        amp
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fixtures::ECHO;
+    use crate::snapshot::{Snapshot, SnapshotError};
+    use crate::{Amp, RunState};
+
+    fn echo() -> Amp {
+        Amp::new(ECHO.to_vec())
+    }
+
+    #[test]
+    fn restores_to_the_snapshot_point() {
+        let mut amp = echo();
+        amp.extend_input(vec![1, 2]);
+        assert_eq!(amp.resume(), Ok(RunState::Output(1)));
+
+        let snapshot = amp.snapshot();
+        assert_eq!(amp.resume(), Ok(RunState::Output(2)));
+        amp.push_input(0);
+        assert_eq!(amp.resume(), Ok(RunState::Halted));
+
+        amp.restore(&snapshot);
+        assert_eq!(amp.resume(), Ok(RunState::Output(2)));
+    }
+
+    #[test]
+    fn forks_run_independently() {
+        let mut amp = echo();
+        amp.push_input(5);
+        amp.resume().unwrap();
+
+        let mut fork = amp.fork();
+        fork.push_input(0);
+        amp.push_input(6);
+
+        assert_eq!(fork.resume(), Ok(RunState::Halted));
+        assert_eq!(amp.resume(), Ok(RunState::Output(6)));
+        assert_eq!(fork.memory().get(11), 0);
+        assert_eq!(amp.memory().get(11), 6);
+    }
+
+    #[test]
+    fn round_trips_through_text() {
+        let mut amp = echo();
+        amp.extend_input(vec![7, 8]);
+        amp.resume().unwrap();
+        amp.memory_mut().set(1_000_000, -3);
+
+        let text = amp.snapshot().to_string();
+        let restored = text.parse::<Snapshot>().unwrap();
+        let mut resumed = Amp::from(restored);
+
+        assert_eq!(resumed.snapshot().to_string(), text);
+        assert_eq!(resumed.memory().get(1_000_000), -3);
+        assert_eq!(resumed.resume(), Ok(RunState::Output(8)));
+    }
+
+    #[test]
+    fn rejects_malformed_snapshots() {
+        let text = "intcode-snapshot 1\npc 0\nrelative x\nmemory 99\n";
+
+        match text.parse::<Snapshot>() {
+            Err(SnapshotError::Malformed { line, .. }) => assert_eq!(line, 3),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(matches!(
+            "pc 0".parse::<Snapshot>(),
+            Err(SnapshotError::MissingHeader)
+        ));
+
+        let text =
+            "intcode-snapshot 1\npc 0\nrelative 0\nmemory 99\nsparse 18446744073709551615=1\n";
+        assert!(matches!(
+            text.parse::<Snapshot>(),
+            Err(SnapshotError::Malformed { line: 5, .. })
+        ));
+    }
+}
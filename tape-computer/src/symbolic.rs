@@ -0,0 +1,680 @@
+use crate::exec::{self, Machine};
+use crate::word::Word;
+use crate::{IntcodeError, Memory, Overflow, RunState};
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::rc::Rc;
+
+// A value on the symbolic tape. Constants fold as they are built, so a
+// program that never touches a symbol runs entirely on `Const`s.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Expr {
+    Const(i64),
+    Var(usize),
+    Add(Rc<Expr>, Rc<Expr>),
+    Mul(Rc<Expr>, Rc<Expr>),
+    Lt(Rc<Expr>, Rc<Expr>),
+    Eq(Rc<Expr>, Rc<Expr>),
+    // Whatever the tape held at an address that depends on a symbol.
+    Load(Rc<Expr>),
+}
+
+impl Expr {
+    pub fn constant(&self) -> Option<i64> {
+        match self {
+            Expr::Const(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+// Unwritten memory reads as zero, as it does on `Amp`.
+impl Default for Expr {
+    fn default() -> Self {
+        Expr::Const(0)
+    }
+}
+
+// Lets `Symbolic` run on the same instruction code as `Amp`. Constants fold
+// under the same overflow policy as `Amp`; anything else builds a node.
+impl Word for Rc<Expr> {
+    fn add(&self, other: &Self, overflow: Overflow, pc: usize) -> Result<Self, IntcodeError> {
+        Ok(match (self.constant(), other.constant()) {
+            (Some(0), _) => Rc::clone(other),
+            (_, Some(0)) => Rc::clone(self),
+            (Some(x), Some(y)) => Rc::new(Expr::Const(overflow.add(x, y, pc)?)),
+            _ => Rc::new(Expr::Add(Rc::clone(self), Rc::clone(other))),
+        })
+    }
+
+    fn mul(&self, other: &Self, overflow: Overflow, pc: usize) -> Result<Self, IntcodeError> {
+        Ok(match (self.constant(), other.constant()) {
+            (Some(0), _) | (_, Some(0)) => Rc::new(Expr::Const(0)),
+            (Some(1), _) => Rc::clone(other),
+            (_, Some(1)) => Rc::clone(self),
+            (Some(x), Some(y)) => Rc::new(Expr::Const(overflow.mul(x, y, pc)?)),
+            _ => Rc::new(Expr::Mul(Rc::clone(self), Rc::clone(other))),
+        })
+    }
+
+    fn less(&self, other: &Self) -> Self {
+        match (self.constant(), other.constant()) {
+            (Some(x), Some(y)) => Rc::new(Expr::Const((x < y) as i64)),
+            _ => Rc::new(Expr::Lt(Rc::clone(self), Rc::clone(other))),
+        }
+    }
+
+    fn equal(&self, other: &Self) -> Self {
+        match (self.constant(), other.constant()) {
+            (Some(x), Some(y)) => Rc::new(Expr::Const((x == y) as i64)),
+            _ => Rc::new(Expr::Eq(Rc::clone(self), Rc::clone(other))),
+        }
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        self.constant()
+    }
+
+    fn is_zero(&self) -> Option<bool> {
+        self.constant().map(|value| value == 0)
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Symbol {
+    pub name: String,
+    pub range: RangeInclusive<i64>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SymbolicError {
+    Intcode(IntcodeError),
+    // A jump condition, jump target or opcode depends on a symbol.
+    SymbolicControl { pc: usize },
+    // A write or the relative base depends on a symbol.
+    SymbolicAddress { pc: usize },
+    InputExhausted { pc: usize },
+    // The expression multiplies symbols together, compares them, or reads
+    // through a symbolic address, or its coefficients overflow an i128.
+    Nonlinear,
+    // Solving would mean trying more than `SEARCH_LIMIT` combinations of
+    // values for the symbols that aren't solved for directly.
+    SearchTooLarge,
+}
+
+impl fmt::Display for SymbolicError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SymbolicError::Intcode(error) => write!(f, "{}", error),
+            SymbolicError::SymbolicControl { pc } => {
+                write!(f, "control flow depends on a symbol at pc {}", pc)
+            }
+            SymbolicError::SymbolicAddress { pc } => {
+                write!(f, "write address depends on a symbol at pc {}", pc)
+            }
+            SymbolicError::InputExhausted { pc } => write!(f, "out of input at pc {}", pc),
+            SymbolicError::Nonlinear => write!(f, "expression is not linear in its symbols"),
+            SymbolicError::SearchTooLarge => {
+                write!(f, "too many combinations of symbol values to search")
+            }
+        }
+    }
+}
+
+impl Error for SymbolicError {}
+
+impl From<IntcodeError> for SymbolicError {
+    fn from(error: IntcodeError) -> Self {
+        SymbolicError::Intcode(error)
+    }
+}
+
+// Runs a program with some memory cells or inputs left as named symbols and
+// records what each cell and output works out to in terms of them. Control
+// flow has to stay concrete; only the data can be symbolic.
+#[derive(Debug, Clone)]
+pub struct Symbolic {
+    pc: usize,
+    relative: Rc<Expr>,
+    mem: Memory<Rc<Expr>>,
+    input_buffer: VecDeque<Rc<Expr>>,
+    outputs: Vec<Rc<Expr>>,
+    symbols: Vec<Symbol>,
+    overflow: Overflow,
+}
+
+impl Symbolic {
+    pub fn new(program: &[i64]) -> Self {
+        Self {
+            pc: 0,
+            relative: Rc::default(),
+            mem: Memory::new(
+                program
+                    .iter()
+                    .map(|&word| Rc::new(Expr::Const(word)))
+                    .collect(),
+            ),
+            input_buffer: VecDeque::new(),
+            outputs: Vec::new(),
+            symbols: Vec::new(),
+            overflow: Overflow::default(),
+        }
+    }
+
+    pub fn set_overflow(&mut self, overflow: Overflow) {
+        self.overflow = overflow;
+    }
+
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    // Replaces the word at `addr` with a new symbol, returning its index.
+    pub fn symbol_at(&mut self, addr: usize, name: &str, range: RangeInclusive<i64>) -> usize {
+        let var = self.symbol(name, range);
+        self.mem.set(addr, var);
+
+        self.symbols.len() - 1
+    }
+
+    // Queues a new symbol as the next input, returning its index.
+    pub fn symbolic_input(&mut self, name: &str, range: RangeInclusive<i64>) -> usize {
+        let var = self.symbol(name, range);
+        self.input_buffer.push_back(var);
+
+        self.symbols.len() - 1
+    }
+
+    pub fn push_input(&mut self, value: i64) {
+        self.input_buffer.push_back(Rc::new(Expr::Const(value)));
+    }
+
+    pub fn memory(&self, addr: usize) -> Rc<Expr> {
+        self.mem.get(addr)
+    }
+
+    pub fn outputs(&self) -> &[Rc<Expr>] {
+        &self.outputs
+    }
+
+    // Runs until the program halts. Outputs are collected rather than
+    // returned one at a time, and running out of input is an error.
+    pub fn run(&mut self) -> Result<(), SymbolicError> {
+        loop {
+            match self.step()? {
+                Some(RunState::Output(value)) => self.outputs.push(value),
+                Some(RunState::NeedsInput) => {
+                    return Err(SymbolicError::InputExhausted { pc: self.pc })
+                }
+                Some(RunState::Halted) => return Ok(()),
+                None => {}
+            }
+        }
+    }
+
+    // Same contract as `Amp::step`. Outputs are returned, not collected.
+    pub fn step(&mut self) -> Result<Option<RunState<Rc<Expr>>>, SymbolicError> {
+        exec::step(self).map(exec::run_state)
+    }
+
+    // Finds values for every symbol, within their ranges, that make `expr`
+    // equal `target`. The last two symbols with a nonzero coefficient are
+    // solved for directly; any before them are enumerated, smallest value
+    // first, skipping values the rest can't make up. Where there's a choice,
+    // earlier symbols take the smallest value that works, and symbols that
+    // don't appear take their lowest.
+    pub fn solve(&self, expr: &Expr, target: i64) -> Result<Option<Vec<i64>>, SymbolicError> {
+        let mut cache = HashMap::new();
+        let (coefficients, constant) = linear(expr, self.symbols.len(), &mut cache)?;
+
+        if self.symbols.iter().any(|symbol| symbol.range.is_empty()) {
+            return Ok(None);
+        }
+
+        let mut values = self
+            .symbols
+            .iter()
+            .map(|symbol| *symbol.range.start() as i128)
+            .collect::<Vec<_>>();
+        let terms = (0..self.symbols.len())
+            .filter(|&i| coefficients[i] != 0)
+            .map(|i| {
+                let range = &self.symbols[i].range;
+                (
+                    i,
+                    coefficients[i],
+                    *range.start() as i128,
+                    *range.end() as i128,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let enumerated = &terms[..terms.len().saturating_sub(2)];
+        let combinations = enumerated.iter().try_fold(1_u128, |n, &(_, _, low, high)| {
+            n.checked_mul((high - low + 1) as u128)
+        });
+        if combinations.filter(|&n| n <= SEARCH_LIMIT).is_none() {
+            return Err(SymbolicError::SearchTooLarge);
+        }
+
+        let remaining = (target as i128)
+            .checked_sub(constant)
+            .ok_or(SymbolicError::Nonlinear)?;
+        let found = search(&terms, remaining, &mut values)?;
+
+        Ok(found.then(|| values.into_iter().map(|value| value as i64).collect()))
+    }
+
+    fn symbol(&mut self, name: &str, range: RangeInclusive<i64>) -> Rc<Expr> {
+        self.symbols.push(Symbol {
+            name: name.to_string(),
+            range,
+        });
+
+        Rc::new(Expr::Var(self.symbols.len() - 1))
+    }
+}
+
+impl Machine for Symbolic {
+    type Word = Rc<Expr>;
+    type Error = SymbolicError;
+
+    fn pc(&self) -> usize {
+        self.pc
+    }
+
+    fn set_pc(&mut self, pc: usize) {
+        self.pc = pc;
+    }
+
+    fn overflow(&self) -> Overflow {
+        self.overflow
+    }
+
+    fn relative(&self) -> &Rc<Expr> {
+        &self.relative
+    }
+
+    fn set_relative(&mut self, relative: Rc<Expr>) -> Result<(), SymbolicError> {
+        if relative.constant().is_none() {
+            return Err(SymbolicError::SymbolicAddress { pc: self.pc });
+        }
+
+        self.relative = relative;
+        Ok(())
+    }
+
+    fn memory(&self) -> &Memory<Rc<Expr>> {
+        &self.mem
+    }
+
+    fn store(&mut self, addr: usize, value: Rc<Expr>) {
+        self.mem.set(addr, value);
+    }
+
+    fn input(&mut self) -> Option<Rc<Expr>> {
+        self.input_buffer.pop_front()
+    }
+
+    fn unknown_control(&self) -> SymbolicError {
+        SymbolicError::SymbolicControl { pc: self.pc }
+    }
+
+    fn unknown_address(&self) -> SymbolicError {
+        SymbolicError::SymbolicAddress { pc: self.pc }
+    }
+
+    // Reading through an address that depends on a symbol is allowed; the
+    // result just can't be solved for.
+    fn load_unknown(&self, addr: Rc<Expr>) -> Result<Rc<Expr>, SymbolicError> {
+        Ok(Rc::new(Expr::Load(addr)))
+    }
+}
+
+type Linear = (Vec<i128>, i128);
+
+// Coefficients per symbol plus a constant. Subtrees are shared between
+// cells, so results are cached by node to keep this linear in the DAG.
+fn linear(
+    expr: &Expr,
+    symbols: usize,
+    cache: &mut HashMap<*const Expr, Linear>,
+) -> Result<Linear, SymbolicError> {
+    let key = expr as *const Expr;
+    if let Some(form) = cache.get(&key) {
+        return Ok(form.clone());
+    }
+
+    let form = match expr {
+        Expr::Const(value) => (vec![0; symbols], *value as i128),
+        Expr::Var(i) => {
+            let mut coefficients = vec![0; symbols];
+            coefficients[*i] = 1;
+            (coefficients, 0)
+        }
+        Expr::Add(a, b) => {
+            let (mut coefficients, constant) = linear(a, symbols, cache)?;
+            let (other, other_constant) = linear(b, symbols, cache)?;
+            for (c, o) in coefficients.iter_mut().zip(other) {
+                *c = c.checked_add(o).ok_or(SymbolicError::Nonlinear)?;
+            }
+
+            let constant = constant
+                .checked_add(other_constant)
+                .ok_or(SymbolicError::Nonlinear)?;
+            (coefficients, constant)
+        }
+        Expr::Mul(a, b) => {
+            let (a, b) = (linear(a, symbols, cache)?, linear(b, symbols, cache)?);
+            let ((coefficients, constant), factor) = match (&a, &b) {
+                (_, (other, factor)) if other.iter().all(|&c| c == 0) => (a, *factor),
+                ((other, factor), _) if other.iter().all(|&c| c == 0) => (b, *factor),
+                _ => return Err(SymbolicError::Nonlinear),
+            };
+
+            let scale = |value: i128| value.checked_mul(factor).ok_or(SymbolicError::Nonlinear);
+            (
+                coefficients
+                    .into_iter()
+                    .map(scale)
+                    .collect::<Result<_, _>>()?,
+                scale(constant)?,
+            )
+        }
+        Expr::Lt(_, _) | Expr::Eq(_, _) | Expr::Load(_) => return Err(SymbolicError::Nonlinear),
+    };
+
+    cache.insert(key, form.clone());
+
+    Ok(form)
+}
+
+// The most combinations of enumerated symbol values `solve` will try.
+const SEARCH_LIMIT: u128 = 1 << 24;
+
+type Term = (usize, i128, i128, i128);
+
+// Depth-first over the terms in order; `remaining` is what the terms not yet
+// assigned still have to add up to. Arithmetic that overflows an i128 makes
+// the expression count as nonlinear.
+fn search(terms: &[Term], remaining: i128, values: &mut [i128]) -> Result<bool, SymbolicError> {
+    match *terms {
+        [] => Ok(remaining == 0),
+        [(i, coefficient, low, high)] => {
+            // Only overflows when the quotient couldn't be in range anyway.
+            let value = match (
+                remaining.checked_rem(coefficient),
+                remaining.checked_div(coefficient),
+            ) {
+                (Some(0), Some(value)) if (low..=high).contains(&value) => value,
+                _ => return Ok(false),
+            };
+
+            values[i] = value;
+            Ok(true)
+        }
+        [x, y] => match solve_pair(&x, &y, remaining)? {
+            Some((a, b)) => {
+                values[x.0] = a;
+                values[y.0] = b;
+                Ok(true)
+            }
+            None => Ok(false),
+        },
+        [(i, coefficient, low, high), ref rest @ ..] => {
+            // The smallest and largest sums the remaining terms can reach.
+            let (min, max) = rest
+                .iter()
+                .try_fold((0_i128, 0_i128), |(min, max), &(_, c, low, high)| {
+                    let (a, b) = (c.checked_mul(low)?, c.checked_mul(high)?);
+                    Some((min.checked_add(a.min(b))?, max.checked_add(a.max(b))?))
+                })
+                .ok_or(SymbolicError::Nonlinear)?;
+
+            for value in low..=high {
+                let left = match coefficient
+                    .checked_mul(value)
+                    .and_then(|product| remaining.checked_sub(product))
+                {
+                    Some(left) => left,
+                    // Too far from `remaining` for the rest to make up.
+                    None => continue,
+                };
+
+                if (min..=max).contains(&left) {
+                    values[i] = value;
+                    if search(rest, left, values)? {
+                        return Ok(true);
+                    }
+                }
+            }
+
+            Ok(false)
+        }
+    }
+}
+
+// Solves a·x + b·y = r within both terms' ranges, taking the smallest x.
+// Every solution is x₀ + k·(b/g), y₀ − k·(a/g) for g = gcd(a, b), so this
+// works out the range of k that keeps both in bounds rather than trying
+// values one by one.
+fn solve_pair(x: &Term, y: &Term, r: i128) -> Result<Option<(i128, i128)>, SymbolicError> {
+    let (&(_, a, x_low, x_high), &(_, b, y_low, y_high)) = (x, y);
+    let overflow = || SymbolicError::Nonlinear;
+
+    if a == i128::MIN || b == i128::MIN {
+        return Err(overflow());
+    }
+
+    let (g, s) = extended_gcd(a, b);
+    if r % g != 0 {
+        return Ok(None);
+    }
+
+    // x repeats every `period`; the smallest x in range comes first.
+    let period = (b / g).abs();
+    let residue = (s.rem_euclid(period) as u128)
+        .checked_mul((r / g).rem_euclid(period) as u128)
+        .ok_or_else(overflow)?
+        % period as u128;
+    let offset = (residue as i128)
+        .checked_sub(x_low)
+        .ok_or_else(overflow)?
+        .rem_euclid(period);
+    let x_first = x_low.checked_add(offset).ok_or_else(overflow)?;
+    if x_first > x_high {
+        return Ok(None);
+    }
+
+    // Each step of x by `period` moves y by `-y_step`.
+    let y_first = r
+        .checked_sub(a.checked_mul(x_first).ok_or_else(overflow)?)
+        .ok_or_else(overflow)?
+        / b;
+    let y_step = a / g * b.signum();
+
+    let steps = |from: i128, to: i128, step: i128| -> Option<i128> {
+        from.checked_sub(to)?.checked_div(step)
+    };
+    let k_max_x = steps(x_high, x_first, period).ok_or_else(overflow)?;
+    let (k_min_y, k_max_y) = if y_step > 0 {
+        (
+            ceil_div(y_first.checked_sub(y_high).ok_or_else(overflow)?, y_step),
+            (y_first.checked_sub(y_low).ok_or_else(overflow)?).div_euclid(y_step),
+        )
+    } else {
+        let y_step = -y_step;
+        (
+            ceil_div(y_low.checked_sub(y_first).ok_or_else(overflow)?, y_step),
+            (y_high.checked_sub(y_first).ok_or_else(overflow)?).div_euclid(y_step),
+        )
+    };
+
+    let (k_min, k_max) = (k_min_y.max(0), k_max_y.min(k_max_x));
+    if k_min > k_max {
+        return Ok(None);
+    }
+
+    let x = x_first
+        .checked_add(k_min.checked_mul(period).ok_or_else(overflow)?)
+        .ok_or_else(overflow)?;
+    let y = y_first
+        .checked_sub(k_min.checked_mul(y_step).ok_or_else(overflow)?)
+        .ok_or_else(overflow)?;
+
+    Ok(Some((x, y)))
+}
+
+// gcd(a, b), positive, and s with a·s ≡ gcd (mod b), for nonzero a and b
+// other than i128::MIN.
+fn extended_gcd(a: i128, b: i128) -> (i128, i128) {
+    let (mut r0, mut r1) = (a, b);
+    let (mut s0, mut s1) = (1, 0);
+
+    while r1 != 0 {
+        let q = r0 / r1;
+        let r = r0 - q * r1;
+        r0 = r1;
+        r1 = r;
+        let s = s0 - q * s1;
+        s0 = s1;
+        s1 = s;
+    }
+
+    if r0 < 0 {
+        (-r0, -s0)
+    } else {
+        (r0, s0)
+    }
+}
+
+// Division rounding up, for a positive divisor.
+fn ceil_div(n: i128, d: i128) -> i128 {
+    let quotient = n.div_euclid(d);
+    if n.rem_euclid(d) == 0 {
+        quotient
+    } else {
+        quotient + 1
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::symbolic::{Expr, Symbolic, SymbolicError};
+    use crate::{IntcodeError, Overflow};
+
+    #[test]
+    fn solves_memory_symbols() {
+        // [0] = [13] * 100 + [14] + 7
+        let program = vec![1002, 13, 100, 0, 1, 0, 14, 0, 1001, 0, 7, 0, 99, 0, 0];
+        let mut machine = Symbolic::new(&program);
+        machine.symbol_at(13, "noun", 0..=99);
+        machine.symbol_at(14, "verb", 0..=99);
+
+        machine.run().unwrap();
+        let actual = machine.solve(&machine.memory(0), 4_258).unwrap();
+
+        assert_eq!(actual, Some(vec![42, 51]));
+        assert_eq!(machine.solve(&machine.memory(0), 10_007).unwrap(), None);
+    }
+
+    #[test]
+    fn solves_symbolic_inputs_through_outputs() {
+        // Prints 3 * (input + 5).
+        let program = vec![3, 13, 1001, 13, 5, 13, 1002, 13, 3, 13, 4, 13, 99, 0];
+        let mut machine = Symbolic::new(&program);
+        machine.symbolic_input("x", -100..=100);
+
+        machine.run().unwrap();
+        let output = machine.outputs()[0].clone();
+
+        assert_eq!(machine.solve(&output, -27).unwrap(), Some(vec![-14]));
+        assert_eq!(machine.solve(&output, 28).unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_symbolic_control_and_products() {
+        let mut machine = Symbolic::new(&[3, 7, 1005, 7, 0, 99, 0, 0]);
+        machine.symbolic_input("x", 0..=1);
+        assert_eq!(machine.run(), Err(SymbolicError::SymbolicControl { pc: 2 }));
+
+        let mut machine = Symbolic::new(&[2, 5, 6, 0, 99, 0, 0]);
+        machine.symbol_at(5, "a", 1..=9);
+        machine.symbol_at(6, "b", 1..=9);
+        machine.run().unwrap();
+        assert_eq!(
+            machine.solve(&machine.memory(0), 12),
+            Err(SymbolicError::Nonlinear)
+        );
+    }
+
+    #[test]
+    fn overflows_like_the_machine() {
+        let program = [1101, i64::MAX, 1, 0, 99];
+
+        let mut machine = Symbolic::new(&program);
+        assert_eq!(
+            machine.run(),
+            Err(SymbolicError::Intcode(IntcodeError::Overflow { pc: 0 }))
+        );
+
+        let mut machine = Symbolic::new(&program);
+        machine.set_overflow(Overflow::Wrapping);
+        machine.run().unwrap();
+        assert_eq!(*machine.memory(0), Expr::Const(i64::MIN));
+    }
+
+    #[test]
+    fn runs_code_written_past_the_program() {
+        // Writes a halt at 100 and jumps to it.
+        let mut machine = Symbolic::new(&[1101, 99, 0, 100, 1105, 1, 100]);
+
+        assert_eq!(machine.run(), Ok(()));
+    }
+
+    #[test]
+    fn solves_two_full_width_symbols_directly() {
+        // [0] = [13] * 3 + [14] * 5
+        let program = vec![1002, 13, 3, 13, 1002, 14, 5, 14, 1, 13, 14, 0, 99, 0, 0];
+        let mut machine = Symbolic::new(&program);
+        machine.symbol_at(13, "x", i64::MIN..=i64::MAX);
+        machine.symbol_at(14, "y", i64::MIN..=i64::MAX);
+
+        machine.run().unwrap();
+        let actual = machine.solve(&machine.memory(0), 1).unwrap().unwrap();
+
+        let (x, y) = (actual[0] as i128, actual[1] as i128);
+        assert_eq!(3 * x + 5 * y, 1);
+        // The smallest x that works; the next one down is out of range.
+        assert!(x - 5 < i64::MIN as i128);
+    }
+
+    #[test]
+    fn refuses_searches_too_large_or_overflowing() {
+        // [0] = [13] + [14] + [15]
+        let program = vec![1, 13, 14, 0, 1, 0, 15, 0, 99, 0, 0, 0, 0, 0, 0, 0];
+        let mut machine = Symbolic::new(&program);
+        machine.symbol_at(13, "x", i64::MIN..=i64::MAX);
+        machine.symbol_at(14, "y", 0..=9);
+        machine.symbol_at(15, "z", 0..=9);
+        machine.run().unwrap();
+        assert_eq!(
+            machine.solve(&machine.memory(0), 0),
+            Err(SymbolicError::SearchTooLarge)
+        );
+
+        // [0] = [13] * i64::MAX * i64::MAX + [14]
+        let max = i64::MAX;
+        let program = vec![1002, 13, max, 13, 1002, 13, max, 13, 1, 13, 14, 0, 99, 0, 0];
+        let mut machine = Symbolic::new(&program);
+        machine.symbol_at(13, "x", i64::MIN..=i64::MAX);
+        machine.symbol_at(14, "y", 0..=1);
+        machine.run().unwrap();
+        assert_eq!(
+            machine.solve(&machine.memory(0), 1),
+            Err(SymbolicError::Nonlinear)
+        );
+    }
+}
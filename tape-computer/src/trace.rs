@@ -0,0 +1,201 @@
+use crate::disasm::format_operand;
+use crate::Mode;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TraceEvent {
+    pub pc: usize,
+    pub mnemonic: String,
+    pub modes: Vec<Mode>,
+    pub params: Vec<i64>,
+    pub reads: Vec<i64>,
+    pub write: Option<(usize, i64)>,
+}
+
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let operands = self
+            .modes
+            .iter()
+            .zip(&self.params)
+            .map(|(&mode, &param)| format_operand(mode, param))
+            .collect::<Vec<_>>();
+
+        write!(
+            f,
+            "{:04}: {:<4}{:<30}",
+            self.pc,
+            self.mnemonic.to_uppercase(),
+            operands.join(", ")
+        )?;
+
+        let reads = self.reads.iter().map(i64::to_string).collect::<Vec<_>>();
+        write!(f, " reads=({})", reads.join(", "))?;
+
+        if let Some((addr, value)) = self.write {
+            write!(f, " [{}]={}", addr, value)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub trait Tracer {
+    fn trace(&mut self, event: &TraceEvent);
+}
+
+impl Tracer for Vec<TraceEvent> {
+    fn trace(&mut self, event: &TraceEvent) {
+        self.push(event.clone());
+    }
+}
+
+impl<F: FnMut(&TraceEvent)> Tracer for F {
+    fn trace(&mut self, event: &TraceEvent) {
+        self(event)
+    }
+}
+
+// Writes one line per executed instruction; I/O errors stop the log but not
+// the machine.
+pub struct WriteTracer<W: io::Write> {
+    sink: W,
+    failed: bool,
+}
+
+impl<W: io::Write> WriteTracer<W> {
+    pub fn new(sink: W) -> Self {
+        Self {
+            sink,
+            failed: false,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.sink
+    }
+}
+
+impl<W: io::Write> Tracer for WriteTracer<W> {
+    fn trace(&mut self, event: &TraceEvent) {
+        if !self.failed {
+            self.failed = writeln!(self.sink, "{}", event).is_err();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Profile {
+    pub total: u64,
+    pub by_address: HashMap<usize, u64>,
+    pub by_opcode: HashMap<String, u64>,
+}
+
+impl Profile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn hottest_addresses(&self, count: usize) -> Vec<(usize, u64)> {
+        let mut addresses = self
+            .by_address
+            .iter()
+            .map(|(&addr, &hits)| (addr, hits))
+            .collect::<Vec<_>>();
+        addresses.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        addresses.truncate(count);
+
+        addresses
+    }
+
+    pub fn opcodes(&self) -> Vec<(&str, u64)> {
+        let mut opcodes = self
+            .by_opcode
+            .iter()
+            .map(|(name, &hits)| (name.as_str(), hits))
+            .collect::<Vec<_>>();
+        opcodes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+        opcodes
+    }
+}
+
+impl Tracer for Profile {
+    fn trace(&mut self, event: &TraceEvent) {
+        self.total += 1;
+        *self.by_address.entry(event.pc).or_insert(0) += 1;
+        match self.by_opcode.get_mut(&event.mnemonic) {
+            Some(hits) => *hits += 1,
+            None => {
+                self.by_opcode.insert(event.mnemonic.clone(), 1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::trace::{Profile, TraceEvent, WriteTracer};
+    use crate::{Amp, Mode, RunState};
+
+    #[test]
+    fn records_operands_and_writes() {
+        let mut amp = Amp::new(vec![1001, 5, 7, 5, 99, 10]);
+        let mut events = Vec::new();
+
+        assert_eq!(amp.resume_traced(&mut events), Ok(RunState::Halted));
+
+        assert_eq!(
+            events,
+            vec![TraceEvent {
+                pc: 0,
+                mnemonic: "add".to_string(),
+                modes: vec![Mode::Position, Mode::Immediate, Mode::Position],
+                params: vec![5, 7, 5],
+                reads: vec![10, 7],
+                write: Some((5, 17)),
+            }]
+        );
+    }
+
+    #[test]
+    fn does_not_trace_blocked_input() {
+        let mut amp = Amp::new(vec![3, 0, 99]);
+        let mut events = Vec::new();
+
+        assert_eq!(amp.resume_traced(&mut events), Ok(RunState::NeedsInput));
+        assert!(events.is_empty());
+
+        amp.push_input(4);
+        assert_eq!(amp.resume_traced(&mut events), Ok(RunState::Halted));
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].write, Some((0, 4)));
+    }
+
+    #[test]
+    fn counts_executions_per_address_and_opcode() {
+        // Counts down from 3 and halts.
+        let mut amp = Amp::new(vec![1001, 8, -1, 8, 1005, 8, 0, 99, 3]);
+        let mut profile = Profile::new();
+
+        amp.resume_traced(&mut profile).unwrap();
+
+        assert_eq!(profile.total, 6);
+        assert_eq!(profile.hottest_addresses(2), vec![(0, 3), (4, 3)]);
+        assert_eq!(profile.opcodes(), vec![("add", 3), ("jt", 3)]);
+    }
+
+    #[test]
+    fn writes_a_readable_log() {
+        let mut amp = Amp::new(vec![104, 42, 99]);
+        let mut tracer = WriteTracer::new(Vec::new());
+
+        amp.resume_traced(&mut tracer).unwrap();
+
+        let log = String::from_utf8(tracer.into_inner()).unwrap();
+        assert!(log.starts_with("0000: OUT #42"));
+        assert!(log.trim_end().ends_with("reads=(42)"));
+    }
+}
@@ -0,0 +1,229 @@
+use crate::exec::{self, Machine};
+use crate::{IntcodeError, Memory, Overflow, RunState};
+use num_bigint::BigInt;
+use num_traits::{ToPrimitive, Zero};
+use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::fmt::Debug;
+
+// A value a machine can compute with. Fixed-width words follow the machine's
+// `Overflow` policy; words that can't overflow ignore it. Comparisons give a
+// word, 1 or 0, since that's what the machine stores.
+pub trait Word: Clone + Debug + Default {
+    fn add(&self, other: &Self, overflow: Overflow, pc: usize) -> Result<Self, IntcodeError>;
+    fn mul(&self, other: &Self, overflow: Overflow, pc: usize) -> Result<Self, IntcodeError>;
+    fn less(&self, other: &Self) -> Self;
+    fn equal(&self, other: &Self) -> Self;
+
+    // The value as a machine integer, if it is one that fits.
+    fn to_i64(&self) -> Option<i64>;
+
+    // None when the word's value isn't known.
+    fn is_zero(&self) -> Option<bool>;
+}
+
+macro_rules! fixed_width_word {
+    ($($word:ty),*) => {$(
+        impl Word for $word {
+            #[inline]
+            fn add(&self, other: &Self, overflow: Overflow, pc: usize) -> Result<Self, IntcodeError> {
+                match overflow {
+                    Overflow::Checked => self.checked_add(*other).ok_or(IntcodeError::Overflow { pc }),
+                    Overflow::Wrapping => Ok(self.wrapping_add(*other)),
+                    Overflow::Saturating => Ok(self.saturating_add(*other)),
+                }
+            }
+
+            #[inline]
+            fn mul(&self, other: &Self, overflow: Overflow, pc: usize) -> Result<Self, IntcodeError> {
+                match overflow {
+                    Overflow::Checked => self.checked_mul(*other).ok_or(IntcodeError::Overflow { pc }),
+                    Overflow::Wrapping => Ok(self.wrapping_mul(*other)),
+                    Overflow::Saturating => Ok(self.saturating_mul(*other)),
+                }
+            }
+
+            #[inline]
+            fn less(&self, other: &Self) -> Self {
+                (self < other) as $word
+            }
+
+            #[inline]
+            fn equal(&self, other: &Self) -> Self {
+                (self == other) as $word
+            }
+
+            #[inline]
+            fn to_i64(&self) -> Option<i64> {
+                i64::try_from(*self).ok()
+            }
+
+            #[inline]
+            fn is_zero(&self) -> Option<bool> {
+                Some(*self == 0)
+            }
+        }
+    )*};
+}
+
+fixed_width_word!(i32, i64, i128);
+
+impl Word for BigInt {
+    fn add(&self, other: &Self, _: Overflow, _: usize) -> Result<Self, IntcodeError> {
+        Ok(self + other)
+    }
+
+    fn mul(&self, other: &Self, _: Overflow, _: usize) -> Result<Self, IntcodeError> {
+        Ok(self * other)
+    }
+
+    fn less(&self, other: &Self) -> Self {
+        BigInt::from((self < other) as i64)
+    }
+
+    fn equal(&self, other: &Self) -> Self {
+        BigInt::from((self == other) as i64)
+    }
+
+    fn to_i64(&self) -> Option<i64> {
+        ToPrimitive::to_i64(self)
+    }
+
+    fn is_zero(&self) -> Option<bool> {
+        Some(Zero::is_zero(self))
+    }
+}
+
+// An Intcode machine over any `Word`, for programs that need a narrower or
+// wider word than `Amp`'s i64. It runs the builtin instructions only, without
+// `Amp`'s decode cache. Opcodes, addresses and the pc must still fit in an
+// i64.
+#[derive(Debug, Clone)]
+pub struct WordAmp<W> {
+    pc: usize,
+    relative: W,
+    mem: Memory<W>,
+    input_buffer: VecDeque<W>,
+    overflow: Overflow,
+}
+
+impl<W: Word> WordAmp<W> {
+    pub fn new<V: Into<W>>(program: Vec<V>) -> Self {
+        Self {
+            pc: 0,
+            relative: W::default(),
+            mem: Memory::new(program.into_iter().map(Into::into).collect()),
+            input_buffer: VecDeque::new(),
+            overflow: Overflow::default(),
+        }
+    }
+
+    pub fn set_overflow(&mut self, overflow: Overflow) {
+        self.overflow = overflow;
+    }
+
+    pub fn pc(&self) -> usize {
+        self.pc
+    }
+
+    pub fn memory(&self) -> &Memory<W> {
+        &self.mem
+    }
+
+    pub fn get(&self, addr: usize) -> W {
+        self.mem.get(addr)
+    }
+
+    pub fn push_input<V: Into<W>>(&mut self, value: V) {
+        self.input_buffer.push_back(value.into());
+    }
+
+    pub fn resume(&mut self) -> Result<RunState<W>, IntcodeError> {
+        loop {
+            if let Some(state) = self.step()? {
+                return Ok(state);
+            }
+        }
+    }
+
+    // Same contract as `Amp::step`.
+    pub fn step(&mut self) -> Result<Option<RunState<W>>, IntcodeError> {
+        exec::step(self).map(exec::run_state)
+    }
+}
+
+impl<W: Word> Machine for WordAmp<W> {
+    type Word = W;
+    type Error = IntcodeError;
+
+    fn pc(&self) -> usize {
+        self.pc
+    }
+
+    fn set_pc(&mut self, pc: usize) {
+        self.pc = pc;
+    }
+
+    fn overflow(&self) -> Overflow {
+        self.overflow
+    }
+
+    fn relative(&self) -> &W {
+        &self.relative
+    }
+
+    fn set_relative(&mut self, relative: W) -> Result<(), IntcodeError> {
+        self.relative = relative;
+        Ok(())
+    }
+
+    fn memory(&self) -> &Memory<W> {
+        &self.mem
+    }
+
+    fn store(&mut self, addr: usize, value: W) {
+        self.mem.set(addr, value);
+    }
+
+    fn input(&mut self) -> Option<W> {
+        self.input_buffer.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::fixtures::SQUARE;
+    use crate::word::{Word, WordAmp};
+    use crate::{IntcodeError, Overflow, RunState};
+
+    // Every word of the program fits in an i32.
+    fn square<W: Word + From<i32>>() -> WordAmp<W> {
+        WordAmp::new(SQUARE.iter().map(|&word| word as i32).collect())
+    }
+
+    #[test]
+    fn narrow_words_overflow_by_policy() {
+        let mut amp = square::<i32>();
+        amp.push_input(1 << 16);
+        assert_eq!(amp.resume(), Err(IntcodeError::Overflow { pc: 2 }));
+
+        let mut amp = square::<i32>();
+        amp.set_overflow(Overflow::Saturating);
+        amp.push_input(1 << 16);
+        assert_eq!(amp.resume(), Ok(RunState::Output(i32::MAX)));
+
+        let mut amp = square::<i32>();
+        amp.set_overflow(Overflow::Wrapping);
+        amp.push_input((1 << 16) + 1);
+        assert_eq!(amp.resume(), Ok(RunState::Output((1 << 17) + 1)));
+    }
+
+    #[test]
+    fn wide_words_hold_what_i64_cannot() {
+        let mut amp = square::<i128>();
+        amp.push_input(1_i128 << 40);
+
+        assert_eq!(amp.resume(), Ok(RunState::Output(1 << 80)));
+        assert_eq!(amp.resume(), Ok(RunState::Halted));
+    }
+}
@@ -0,0 +1,186 @@
+use tape_computer::{Amp, RunState};
+
+fn run_to_halt(program: Vec<i64>, inputs: Vec<i64>) -> (Vec<i64>, Amp) {
+    let mut computer = Amp::new(program);
+    computer.extend_input(inputs);
+
+    let mut outputs = computer.outputs();
+    let collected = outputs.by_ref().collect();
+    assert_eq!(outputs.finish(), Ok(RunState::Halted));
+
+    (collected, computer)
+}
+
+fn outputs(program: Vec<i64>, inputs: Vec<i64>) -> Vec<i64> {
+    run_to_halt(program, inputs).0
+}
+
+fn final_memory(program: Vec<i64>) -> Vec<i64> {
+    run_to_halt(program, vec![]).1.memory().to_vec()
+}
+
+fn thruster_signal(program: &[i64], phases: &[i64]) -> i64 {
+    phases.iter().fold(0, |signal, &phase| {
+        outputs(program.to_vec(), vec![phase, signal])[0]
+    })
+}
+
+fn feedback_signal(program: &[i64], phases: &[i64]) -> i64 {
+    let mut amps = phases
+        .iter()
+        .map(|&phase| {
+            let mut amp = Amp::new(program.to_vec());
+            amp.push_input(phase);
+            amp
+        })
+        .collect::<Vec<_>>();
+
+    let mut signal = 0;
+    for amp_id in (0..amps.len()).cycle() {
+        amps[amp_id].push_input(signal);
+
+        match amps[amp_id].resume().unwrap() {
+            RunState::Output(s) => signal = s,
+            _ => break,
+        }
+    }
+
+    signal
+}
+
+#[test]
+fn day2_samples() {
+    assert_eq!(
+        final_memory(vec![1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50]),
+        vec![3500, 9, 10, 70, 2, 3, 11, 0, 99, 30, 40, 50]
+    );
+    assert_eq!(final_memory(vec![1, 0, 0, 0, 99]), vec![2, 0, 0, 0, 99]);
+    assert_eq!(final_memory(vec![2, 3, 0, 3, 99]), vec![2, 3, 0, 6, 99]);
+    assert_eq!(
+        final_memory(vec![2, 4, 4, 5, 99, 0]),
+        vec![2, 4, 4, 5, 99, 9801]
+    );
+    assert_eq!(
+        final_memory(vec![1, 1, 1, 4, 99, 5, 6, 0, 99]),
+        vec![30, 1, 1, 4, 2, 5, 6, 0, 99]
+    );
+}
+
+#[test]
+fn day5_echo_and_modes() {
+    assert_eq!(outputs(vec![3, 0, 4, 0, 99], vec![42]), vec![42]);
+    assert_eq!(
+        final_memory(vec![1002, 4, 3, 4, 33]),
+        vec![1002, 4, 3, 4, 99]
+    );
+    assert_eq!(
+        final_memory(vec![1101, 100, -1, 4, 0]),
+        vec![1101, 100, -1, 4, 99]
+    );
+}
+
+#[test]
+fn day5_comparisons() {
+    let equal_to_8_position = vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8];
+    let less_than_8_position = vec![3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8];
+    let equal_to_8_immediate = vec![3, 3, 1108, -1, 8, 3, 4, 3, 99];
+    let less_than_8_immediate = vec![3, 3, 1107, -1, 8, 3, 4, 3, 99];
+
+    for &(input, equal, less) in &[(7, 0, 1), (8, 1, 0), (9, 0, 0)] {
+        assert_eq!(
+            outputs(equal_to_8_position.clone(), vec![input]),
+            vec![equal]
+        );
+        assert_eq!(
+            outputs(less_than_8_position.clone(), vec![input]),
+            vec![less]
+        );
+        assert_eq!(
+            outputs(equal_to_8_immediate.clone(), vec![input]),
+            vec![equal]
+        );
+        assert_eq!(
+            outputs(less_than_8_immediate.clone(), vec![input]),
+            vec![less]
+        );
+    }
+}
+
+#[test]
+fn day5_jumps() {
+    let position = vec![3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9];
+    let immediate = vec![3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1];
+
+    assert_eq!(outputs(position.clone(), vec![0]), vec![0]);
+    assert_eq!(outputs(position, vec![5]), vec![1]);
+    assert_eq!(outputs(immediate.clone(), vec![0]), vec![0]);
+    assert_eq!(outputs(immediate, vec![5]), vec![1]);
+}
+
+#[test]
+fn day5_compare_to_8() {
+    let program = vec![
+        3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0, 0,
+        1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4, 20,
+        1105, 1, 46, 98, 99,
+    ];
+
+    assert_eq!(outputs(program.clone(), vec![7]), vec![999]);
+    assert_eq!(outputs(program.clone(), vec![8]), vec![1000]);
+    assert_eq!(outputs(program, vec![9]), vec![1001]);
+}
+
+#[test]
+fn day7_serial_chains() {
+    let first = vec![
+        3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
+    ];
+    let second = vec![
+        3, 23, 3, 24, 1002, 24, 10, 24, 1002, 23, -1, 23, 101, 5, 23, 23, 1, 24, 23, 23, 4, 23, 99,
+        0, 0,
+    ];
+    let third = vec![
+        3, 31, 3, 32, 1002, 32, 10, 32, 1001, 31, -2, 31, 1007, 31, 0, 33, 1002, 33, 7, 33, 1, 33,
+        31, 31, 1, 32, 31, 31, 4, 31, 99, 0, 0, 0,
+    ];
+
+    assert_eq!(thruster_signal(&first, &[4, 3, 2, 1, 0]), 43210);
+    assert_eq!(thruster_signal(&second, &[0, 1, 2, 3, 4]), 54321);
+    assert_eq!(thruster_signal(&third, &[1, 0, 4, 3, 2]), 65210);
+}
+
+#[test]
+fn day7_feedback_loops() {
+    let first = vec![
+        3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1, 28,
+        1005, 28, 6, 99, 0, 0, 5,
+    ];
+    let second = vec![
+        3, 52, 1001, 52, -5, 52, 3, 53, 1, 52, 56, 54, 1007, 54, 5, 55, 1005, 55, 26, 1001, 54, -5,
+        54, 1105, 1, 12, 1, 53, 54, 53, 1008, 54, 0, 55, 1001, 55, 1, 55, 2, 53, 55, 53, 4, 53,
+        1001, 56, -1, 56, 1005, 56, 6, 99, 0, 0, 0, 0, 10,
+    ];
+
+    assert_eq!(feedback_signal(&first, &[9, 8, 7, 6, 5]), 139_629_729);
+    assert_eq!(feedback_signal(&second, &[9, 7, 8, 5, 6]), 18216);
+}
+
+#[test]
+fn day9_quine() {
+    let program = vec![
+        109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+    ];
+
+    assert_eq!(outputs(program.clone(), vec![]), program);
+}
+
+#[test]
+fn day9_large_numbers() {
+    let sixteen_digits = outputs(vec![1102, 34_915_192, 34_915_192, 7, 4, 7, 99, 0], vec![]);
+
+    assert_eq!(sixteen_digits, vec![1_219_070_632_396_864]);
+    assert_eq!(
+        outputs(vec![104, 1_125_899_906_842_624, 99], vec![]),
+        vec![1_125_899_906_842_624]
+    );
+}